@@ -0,0 +1,52 @@
+//! Records what this build of the crate was made with, for the `shared-heap` feature: copies of the
+//! crate only share an interner if they were built the same way, since its layout isn't stable.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("-vV")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        // Without the compiler's version, there's nothing to tell this build from another one, so
+        // it gets a fingerprint no other build has.
+        .unwrap_or_else(|| format!("unknown rustc, pid {}", std::process::id()));
+
+    let mut features = env::vars()
+        .filter_map(|(var, _)| var.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        .collect::<Vec<_>>();
+    features.sort();
+
+    // Flags only the linker sees, such as the `-rdynamic` that sharing an interner with plugins
+    // needs, don't change how the crate is compiled.
+    let encoded_flags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    let mut flags = Vec::new();
+    let mut args = encoded_flags.split('\x1f').filter(|arg| !arg.is_empty());
+    while let Some(arg) = args.next() {
+        let codegen = match arg {
+            "-C" => args.next().unwrap_or_default(),
+            _ => arg.strip_prefix("-C").unwrap_or(arg),
+        };
+        if !codegen.starts_with("link-arg") {
+            flags.push(arg);
+            if arg == "-C" {
+                flags.push(codegen);
+            }
+        }
+    }
+
+    let fingerprint = format!(
+        "{} {} [{}] [{}]",
+        version.split_whitespace().collect::<Vec<_>>().join(" "),
+        env::var("TARGET").unwrap_or_default(),
+        features.join(","),
+        flags.join(" "),
+    );
+    println!("cargo:rustc-env=SYMBOL_BUILD_FINGERPRINT={}", fingerprint);
+}
@@ -0,0 +1,314 @@
+//! Chunked storage for interned strings and their headers.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use std::mem::{align_of, size_of, size_of_val, take, ManuallyDrop, MaybeUninit};
+use std::ptr::{copy_nonoverlapping, slice_from_raw_parts};
+use std::sync::Arc;
+use std::{slice, str};
+
+/// The size of each chunk allocated by an arena, in words.
+const CHUNK_WORDS: usize = 2 * 1024;
+
+/// Strings longer than this get an allocation of their own instead of going in a chunk next to
+/// their header, so that one long string can't waste most of a chunk.
+const MAX_CHUNKED_LEN: usize = CHUNK_WORDS * size_of::<Word>() / 16;
+
+/// The unit chunks are allocated in, which also sets their alignment.
+type Word = MaybeUninit<u64>;
+
+/// A bump allocator for headers followed by the strings they describe.
+///
+/// Allocations are handed out as `&'static` references, and live until the arena is cleared.
+#[derive(Default)]
+pub(crate) struct Arena {
+    /// The unused end of the current chunk.
+    free: &'static mut [Word],
+
+    /// Every chunk allocated so far.
+    chunks: Vec<*mut [Word]>,
+
+    /// Every string that was too long to go in a chunk.
+    long: Vec<Long>,
+
+    /// The number of bytes allocated so far, for chunks and for strings stored on their own.
+    allocated: usize,
+}
+
+impl Arena {
+    pub const fn new() -> Arena {
+        Arena {
+            free: &mut [],
+            chunks: Vec::new(),
+            long: Vec::new(),
+            allocated: 0,
+        }
+    }
+
+    /// Stores a string in the arena, directly after a header built from the stored copy.
+    ///
+    /// Short strings are copied into the current chunk. Long ones get an allocation of their own,
+    /// which is the string's own buffer if the arena is given ownership of one. Strings that live
+    /// forever already are never copied.
+    pub fn alloc<T>(&mut self, s: Str<'_>, header: impl FnOnce(&'static str) -> T) -> &'static T {
+        assert!(align_of::<T>() <= align_of::<Word>());
+        let header_words = words(size_of::<T>());
+        let s = match s {
+            Str::Static(s) => {
+                let (header_slot, _) = self.take(header_words, header_words);
+                return write(header_slot, header(s));
+            }
+            Str::Borrowed(s) if s.len() <= MAX_CHUNKED_LEN => s,
+            Str::Owned(ref s) if s.len() <= MAX_CHUNKED_LEN => s,
+            Str::Shared(ref s) if s.len() <= MAX_CHUNKED_LEN => s,
+            s => {
+                let (header_slot, _) = self.take(header_words, header_words);
+                let long = Long::new(s);
+                self.allocated += long.size();
+                // SAFETY: The string is only freed by `clear`, `rollback`, or `retain`, whose
+                // callers promise not to use it afterwards.
+                let s = unsafe { long.as_str() };
+                self.long.push(long);
+                return write(header_slot, header(s));
+            }
+        };
+
+        let (header_slot, rest) = self.take(header_words, header_words + words(s.len()));
+        let bytes = rest.as_mut_ptr().cast::<u8>();
+        // SAFETY: `rest` is at least `s.len()` bytes long, and we have the only reference to it.
+        // The bytes are copied from a str, so they're valid UTF-8.
+        let s = unsafe {
+            copy_nonoverlapping(s.as_ptr(), bytes, s.len());
+            str::from_utf8_unchecked(slice::from_raw_parts(bytes, s.len()))
+        };
+        write(header_slot, header(s))
+    }
+
+    /// Returns the number of bytes the arena has allocated, including unused space in its chunks.
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    /// Frees everything the arena has allocated.
+    ///
+    /// # Safety
+    ///
+    /// Nothing the arena has handed out may be used afterwards.
+    pub unsafe fn clear(&mut self) {
+        self.free = &mut [];
+        for chunk in self.chunks.drain(..) {
+            drop(Box::from_raw(chunk));
+        }
+        for long in self.long.drain(..) {
+            long.free();
+        }
+        self.allocated = 0;
+    }
+
+    /// Records how much the arena has allocated, so that it can be rolled back to this point.
+    pub fn mark(&self) -> Mark {
+        Mark {
+            chunks: self.chunks.len(),
+            long: self.long.len(),
+            free: self.free.len(),
+            allocated: self.allocated,
+        }
+    }
+
+    /// Frees everything allocated since a mark was taken.
+    ///
+    /// # Safety
+    ///
+    /// The mark must have come from this arena, and the arena must not have been rolled back to an
+    /// earlier mark or cleared since. Nothing allocated since the mark may be used afterwards.
+    pub unsafe fn rollback(&mut self, mark: &Mark) {
+        self.free = &mut [];
+        for chunk in self.chunks.drain(mark.chunks..) {
+            drop(Box::from_raw(chunk));
+        }
+        for long in self.long.drain(mark.long..) {
+            long.free();
+        }
+        if let Some(&chunk) = self.chunks.last() {
+            // Only reborrow the end of the chunk, since the start still holds live allocations.
+            let start = chunk.cast::<Word>().add(CHUNK_WORDS - mark.free);
+            self.free = slice::from_raw_parts_mut(start, mark.free);
+        }
+        self.allocated = mark.allocated;
+    }
+
+    /// Frees every chunk and long string for which `live` returns false, given the range of
+    /// addresses it covers. The chunk currently being allocated from is always kept.
+    ///
+    /// # Safety
+    ///
+    /// Nothing in the freed storage may be used afterwards, and marks taken before this may not be
+    /// rolled back to.
+    pub unsafe fn retain(&mut self, mut live: impl FnMut(usize, usize) -> bool) {
+        let current = self.chunks.last().map(|&chunk| chunk.cast::<Word>());
+        let mut freed = 0;
+        self.chunks.retain(|&chunk| {
+            let start = chunk.cast::<Word>();
+            let size = CHUNK_WORDS * size_of::<Word>();
+            if Some(start) == current || live(start as usize, start as usize + size) {
+                return true;
+            }
+            drop(Box::from_raw(chunk));
+            freed += size;
+            false
+        });
+        self.long.retain(|long| {
+            let start = long.ptr as usize;
+            if live(start, start + long.len) {
+                return true;
+            }
+            freed += long.size();
+            long.free();
+            false
+        });
+        self.allocated -= freed;
+    }
+
+    /// Takes `len` words from the current chunk, split after the first `header_words`.
+    fn take(
+        &mut self,
+        header_words: usize,
+        len: usize,
+    ) -> (&'static mut [Word], &'static mut [Word]) {
+        if self.free.len() < len {
+            let chunk = Box::into_raw((0..CHUNK_WORDS).map(|_| MaybeUninit::uninit()).collect());
+            self.chunks.push(chunk);
+            // SAFETY: As for long strings in `alloc`.
+            self.free = unsafe { &mut *chunk };
+            self.allocated += size_of_val(self.free);
+        }
+        let (taken, free) = take(&mut self.free).split_at_mut(len);
+        self.free = free;
+        taken.split_at_mut(header_words)
+    }
+}
+
+/// A string to store in an arena, which it can take ownership of instead of copying.
+pub(crate) enum Str<'a> {
+    Borrowed(&'a str),
+    Owned(String),
+    Shared(Arc<str>),
+    Static(&'static str),
+}
+
+impl<'a> Str<'a> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Str::Borrowed(s) => s,
+            Str::Owned(s) => s,
+            Str::Shared(s) => s,
+            Str::Static(s) => s,
+        }
+    }
+}
+
+/// A string with an allocation of its own, which the arena frees.
+struct Long {
+    ptr: *const u8,
+    len: usize,
+    owner: Owner,
+}
+
+/// What a long string's allocation came from, which says how to free it.
+enum Owner {
+    String { capacity: usize },
+    Arc,
+}
+
+impl Long {
+    fn new(s: Str) -> Long {
+        let s = match s {
+            Str::Borrowed(s) | Str::Static(s) => String::from(s),
+            Str::Owned(s) => s,
+            Str::Shared(s) => {
+                let len = s.len();
+                return Long {
+                    ptr: Arc::into_raw(s).cast(),
+                    len,
+                    owner: Owner::Arc,
+                };
+            }
+        };
+        let mut bytes = ManuallyDrop::new(s.into_bytes());
+        Long {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            owner: Owner::String {
+                capacity: bytes.capacity(),
+            },
+        }
+    }
+
+    /// Returns the number of bytes the allocation holds, not counting an `Arc`'s counts.
+    fn size(&self) -> usize {
+        match self.owner {
+            Owner::String { capacity } => capacity,
+            Owner::Arc => self.len,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The string must not be used after it is freed.
+    unsafe fn as_str(&self) -> &'static str {
+        str::from_utf8_unchecked(slice::from_raw_parts(self.ptr, self.len))
+    }
+
+    /// # Safety
+    ///
+    /// Nothing borrowed from the string may be used afterwards.
+    unsafe fn free(&self) {
+        match self.owner {
+            Owner::String { capacity } => {
+                drop(String::from_raw_parts(
+                    self.ptr as *mut u8,
+                    self.len,
+                    capacity,
+                ));
+            }
+            Owner::Arc => {
+                drop(Arc::from_raw(
+                    slice_from_raw_parts(self.ptr, self.len) as *const str
+                ));
+            }
+        }
+    }
+}
+
+/// A point an arena can be rolled back to.
+pub(crate) struct Mark {
+    chunks: usize,
+    long: usize,
+    /// The length of the free end of the last chunk.
+    free: usize,
+    allocated: usize,
+}
+
+// SAFETY: The pointers are to allocations the arena owns, which are only freed by `clear`.
+unsafe impl Send for Arena {}
+
+/// Returns the number of words needed to hold `bytes` bytes.
+fn words(bytes: usize) -> usize {
+    (bytes + size_of::<Word>() - 1) / size_of::<Word>()
+}
+
+/// Moves a value into the start of some words.
+fn write<T>(slot: &'static mut [Word], value: T) -> &'static T {
+    debug_assert!(size_of_val(slot) >= size_of::<T>());
+    let ptr = slot.as_mut_ptr().cast::<T>();
+    // SAFETY: The words are big enough and aligned enough to hold a T (checked in `alloc`), and we
+    // have the only reference to them.
+    unsafe {
+        ptr.write(value);
+        &*ptr
+    }
+}
@@ -0,0 +1,217 @@
+//! Symbols that can be shared between threads and changed without locking.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::ptr::null_mut;
+
+use crate::interner::SymbolData;
+use crate::sync::atomic::{AtomicPtr, Ordering};
+use crate::Symbol;
+
+/// Returns the pointer a symbol is made of, as an `AtomicPtr` holds it.
+fn to_ptr(sym: Symbol) -> *mut SymbolData {
+    sym.data as *const SymbolData as *mut SymbolData
+}
+
+/// Returns the symbol made of a pointer from `to_ptr`.
+///
+/// # Safety
+///
+/// The pointer must be null, or have come from `to_ptr`.
+unsafe fn from_ptr(ptr: *mut SymbolData) -> Option<Symbol> {
+    ptr.as_ref().map(|data| Symbol { data })
+}
+
+/// A symbol that can be changed through a shared reference, like the standard library's atomic
+/// integers.
+///
+/// This is a single atomic pointer, so loading and storing never lock. The orderings mean what they
+/// do for [`AtomicPtr`](core::sync::atomic::AtomicPtr); a symbol is always safe to use once loaded,
+/// whichever ordering loaded it.
+///
+/// ```
+/// # use symbol::{AtomicSymbol, Symbol};
+/// use std::sync::atomic::Ordering;
+///
+/// let current = AtomicSymbol::new(Symbol::from("idle"));
+/// let prev = current.swap(Symbol::from("busy"), Ordering::AcqRel);
+/// assert_eq!(prev, "idle");
+/// assert_eq!(
+///     current.compare_exchange(prev, Symbol::from("done"), Ordering::AcqRel, Ordering::Acquire),
+///     Err(Symbol::from("busy")),
+/// );
+/// assert_eq!(current.load(Ordering::Acquire), "busy");
+/// ```
+pub struct AtomicSymbol {
+    ptr: AtomicPtr<SymbolData>,
+}
+
+impl AtomicSymbol {
+    /// Creates a cell holding a symbol.
+    pub fn new(sym: Symbol) -> AtomicSymbol {
+        AtomicSymbol {
+            ptr: AtomicPtr::new(to_ptr(sym)),
+        }
+    }
+
+    /// Returns the symbol.
+    pub fn load(&self, order: Ordering) -> Symbol {
+        // SAFETY: Only pointers from `to_ptr` are ever stored, and never null.
+        unsafe { from_ptr(self.ptr.load(order)).unwrap_unchecked() }
+    }
+
+    /// Replaces the symbol.
+    pub fn store(&self, sym: Symbol, order: Ordering) {
+        self.ptr.store(to_ptr(sym), order)
+    }
+
+    /// Replaces the symbol, returning the old one.
+    pub fn swap(&self, sym: Symbol, order: Ordering) -> Symbol {
+        // SAFETY: Only pointers from `to_ptr` are ever stored, and never null.
+        unsafe { from_ptr(self.ptr.swap(to_ptr(sym), order)).unwrap_unchecked() }
+    }
+
+    /// Replaces the symbol with `new` if it is `current`, returning the old symbol if it was
+    /// replaced, or the symbol there is instead if it wasn't.
+    pub fn compare_exchange(
+        &self,
+        current: Symbol,
+        new: Symbol,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Symbol, Symbol> {
+        let result = self
+            .ptr
+            .compare_exchange(to_ptr(current), to_ptr(new), success, failure);
+        // SAFETY: Only pointers from `to_ptr` are ever stored, and never null.
+        let to_symbol = |ptr| unsafe { from_ptr(ptr).unwrap_unchecked() };
+        result.map(to_symbol).map_err(to_symbol)
+    }
+
+    /// Returns the symbol, consuming the cell.
+    pub fn into_inner(self) -> Symbol {
+        // SAFETY: Only pointers from `to_ptr` are ever stored, and never null.
+        unsafe { from_ptr(self.ptr.into_inner()).unwrap_unchecked() }
+    }
+}
+
+impl Debug for AtomicSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.load(Ordering::Relaxed), fmt)
+    }
+}
+
+impl Default for AtomicSymbol {
+    fn default() -> AtomicSymbol {
+        AtomicSymbol::new(Symbol::default())
+    }
+}
+
+impl From<Symbol> for AtomicSymbol {
+    fn from(sym: Symbol) -> AtomicSymbol {
+        AtomicSymbol::new(sym)
+    }
+}
+
+/// An [`AtomicSymbol`] that can also be empty.
+///
+/// An empty one can be made in a `const` context, so these can be `static`s, such as caches filled
+/// in when first needed.
+///
+/// ```
+/// # use symbol::{AtomicOptionSymbol, Symbol};
+/// use std::sync::atomic::Ordering;
+///
+/// static LAST_SEEN: AtomicOptionSymbol = AtomicOptionSymbol::new_none();
+/// assert_eq!(LAST_SEEN.load(Ordering::Acquire), None);
+/// LAST_SEEN.store(Some(Symbol::from("seen")), Ordering::Release);
+/// assert_eq!(LAST_SEEN.take(Ordering::AcqRel), Some(Symbol::from("seen")));
+/// assert_eq!(LAST_SEEN.load(Ordering::Acquire), None);
+/// ```
+pub struct AtomicOptionSymbol {
+    ptr: AtomicPtr<SymbolData>,
+}
+
+impl AtomicOptionSymbol {
+    /// Creates a cell holding a symbol, or nothing.
+    pub fn new(sym: Option<Symbol>) -> AtomicOptionSymbol {
+        AtomicOptionSymbol {
+            ptr: AtomicPtr::new(sym.map_or(null_mut(), to_ptr)),
+        }
+    }
+
+    /// Creates an empty cell.
+    pub const fn new_none() -> AtomicOptionSymbol {
+        AtomicOptionSymbol {
+            ptr: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    /// Returns the symbol, if there is one.
+    pub fn load(&self, order: Ordering) -> Option<Symbol> {
+        // SAFETY: Only null and pointers from `to_ptr` are ever stored.
+        unsafe { from_ptr(self.ptr.load(order)) }
+    }
+
+    /// Replaces the symbol.
+    pub fn store(&self, sym: Option<Symbol>, order: Ordering) {
+        self.ptr.store(sym.map_or(null_mut(), to_ptr), order)
+    }
+
+    /// Replaces the symbol, returning the old one.
+    pub fn swap(&self, sym: Option<Symbol>, order: Ordering) -> Option<Symbol> {
+        // SAFETY: Only null and pointers from `to_ptr` are ever stored.
+        unsafe { from_ptr(self.ptr.swap(sym.map_or(null_mut(), to_ptr), order)) }
+    }
+
+    /// Empties the cell, returning the symbol it held.
+    pub fn take(&self, order: Ordering) -> Option<Symbol> {
+        self.swap(None, order)
+    }
+
+    /// Replaces the symbol with `new` if it is `current`, returning the old symbol if it was
+    /// replaced, or the symbol there is instead if it wasn't.
+    pub fn compare_exchange(
+        &self,
+        current: Option<Symbol>,
+        new: Option<Symbol>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<Symbol>, Option<Symbol>> {
+        let result = self.ptr.compare_exchange(
+            current.map_or(null_mut(), to_ptr),
+            new.map_or(null_mut(), to_ptr),
+            success,
+            failure,
+        );
+        // SAFETY: Only null and pointers from `to_ptr` are ever stored.
+        let to_symbol = |ptr| unsafe { from_ptr(ptr) };
+        result.map(to_symbol).map_err(to_symbol)
+    }
+
+    /// Returns the symbol, if there is one, consuming the cell.
+    pub fn into_inner(self) -> Option<Symbol> {
+        // SAFETY: Only null and pointers from `to_ptr` are ever stored.
+        unsafe { from_ptr(self.ptr.into_inner()) }
+    }
+}
+
+impl Debug for AtomicOptionSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.load(Ordering::Relaxed), fmt)
+    }
+}
+
+impl Default for AtomicOptionSymbol {
+    fn default() -> AtomicOptionSymbol {
+        AtomicOptionSymbol::new_none()
+    }
+}
+
+impl From<Option<Symbol>> for AtomicOptionSymbol {
+    fn from(sym: Option<Symbol>) -> AtomicOptionSymbol {
+        AtomicOptionSymbol::new(sym)
+    }
+}
@@ -0,0 +1,206 @@
+//! Sets of symbols stored as one bit per id.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use crate::Symbol;
+
+/// A set of symbols, stored as a bit for each [id](Symbol::id) up to the largest in the set.
+///
+/// Inserting and checking for a symbol is indexing into a vector, and set operations work a word
+/// of 64 symbols at a time without allocating, which suits the sets of variables that dataflow
+/// analyses such as liveness pass around. A set takes an eighth of a byte for every symbol
+/// interned before its largest member, so sets of symbols interned early are the most compact.
+///
+/// ```
+/// # use symbol::{Symbol, SymbolBitSet};
+/// let [a, b, c] = ["a", "b", "c"].map(Symbol::from);
+/// let mut live = [a, b].into_iter().collect::<SymbolBitSet>();
+/// let used = [b, c].into_iter().collect::<SymbolBitSet>();
+/// assert!(live.union_with(&used));
+/// assert!(!live.union_with(&used));
+/// assert_eq!(live.len(), 3);
+///
+/// live.difference_with(&used);
+/// assert!(live.contains(a));
+/// assert!(!live.contains(b));
+/// ```
+#[derive(Clone, Default)]
+pub struct SymbolBitSet {
+    words: Vec<u64>,
+}
+
+impl SymbolBitSet {
+    /// Creates an empty set, which doesn't allocate until something is inserted.
+    pub fn new() -> SymbolBitSet {
+        SymbolBitSet { words: Vec::new() }
+    }
+
+    /// Returns the number of symbols in the set.
+    ///
+    /// As with [`iter`](SymbolBitSet::iter), symbols that have since been removed by
+    /// [`collect`](crate::collect) aren't counted, so this looks up each symbol by its id.
+    ///
+    /// ```
+    /// # use symbol::{Symbol, SymbolBitSet};
+    /// let live = Symbol::from("live");
+    /// let set = [live, Symbol::from("garbage")]
+    ///     .into_iter()
+    ///     .collect::<SymbolBitSet>();
+    /// assert_eq!(set.len(), 2);
+    /// unsafe { symbol::collect(|marker| marker.mark(live)) };
+    /// assert_eq!(set.len(), 1);
+    /// assert_eq!(set.len(), set.iter().count());
+    /// assert!(!set.is_empty());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether there are no symbols in the set.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Removes every symbol, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.words.clear()
+    }
+
+    /// Adds a symbol, returning whether it was new.
+    pub fn insert(&mut self, sym: Symbol) -> bool {
+        let (index, bit) = split(sym);
+        if index >= self.words.len() {
+            self.words.resize(index + 1, 0);
+        }
+        let new = self.words[index] & bit == 0;
+        self.words[index] |= bit;
+        new
+    }
+
+    /// Removes a symbol, returning whether it was present.
+    pub fn remove(&mut self, sym: Symbol) -> bool {
+        let (index, bit) = split(sym);
+        match self.words.get_mut(index) {
+            Some(word) if *word & bit != 0 => {
+                *word &= !bit;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the set contains a symbol.
+    pub fn contains(&self, sym: Symbol) -> bool {
+        let (index, bit) = split(sym);
+        self.words.get(index).map_or(false, |word| word & bit != 0)
+    }
+
+    /// Iterates over the symbols, in id order.
+    ///
+    /// Symbols that have since been removed by [`collect`](crate::collect) are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .filter_map(move |bit| Symbol::from_id((index * 64 + bit) as u32))
+        })
+    }
+
+    /// Adds every symbol in the other set, returning whether this set changed.
+    pub fn union_with(&mut self, other: &SymbolBitSet) -> bool {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        let mut changed = false;
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            changed |= other & !*word != 0;
+            *word |= other;
+        }
+        changed
+    }
+
+    /// Removes every symbol that isn't in the other set, returning whether this set changed.
+    pub fn intersect_with(&mut self, other: &SymbolBitSet) -> bool {
+        let mut changed = false;
+        for (index, word) in self.words.iter_mut().enumerate() {
+            let other = other.words.get(index).copied().unwrap_or(0);
+            changed |= *word & !other != 0;
+            *word &= other;
+        }
+        changed
+    }
+
+    /// Removes every symbol in the other set, returning whether this set changed.
+    pub fn difference_with(&mut self, other: &SymbolBitSet) -> bool {
+        let mut changed = false;
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            changed |= *word & other != 0;
+            *word &= !other;
+        }
+        changed
+    }
+
+    /// Returns whether every symbol in this set is also in the other.
+    pub fn is_subset(&self, other: &SymbolBitSet) -> bool {
+        self.words
+            .iter()
+            .enumerate()
+            .all(|(index, &word)| word & !other.words.get(index).copied().unwrap_or(0) == 0)
+    }
+
+    /// Returns whether the sets have no symbols in common.
+    pub fn is_disjoint(&self, other: &SymbolBitSet) -> bool {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(&word, &other)| word & other == 0)
+    }
+}
+
+/// Returns the index of the word holding a symbol's bit, and the bit within it.
+fn split(sym: Symbol) -> (usize, u64) {
+    let id = sym.id() as usize;
+    (id / 64, 1 << (id % 64))
+}
+
+impl Debug for SymbolBitSet {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl Eq for SymbolBitSet {}
+
+impl Extend<Symbol> for SymbolBitSet {
+    fn extend<I: IntoIterator<Item = Symbol>>(&mut self, iter: I) {
+        for sym in iter {
+            self.insert(sym);
+        }
+    }
+}
+
+impl FromIterator<Symbol> for SymbolBitSet {
+    fn from_iter<I: IntoIterator<Item = Symbol>>(iter: I) -> SymbolBitSet {
+        let mut set = SymbolBitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Sets are equal if they contain the same symbols, however much room either has for others.
+impl PartialEq for SymbolBitSet {
+    fn eq(&self, other: &SymbolBitSet) -> bool {
+        let (short, long) = if self.words.len() <= other.words.len() {
+            (&self.words, &other.words)
+        } else {
+            (&other.words, &self.words)
+        };
+        long[..short.len()] == short[..] && long[short.len()..].iter().all(|&word| word == 0)
+    }
+}
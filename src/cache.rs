@@ -0,0 +1,71 @@
+//! A per-thread cache of recently interned strings, consulted before the global interner.
+
+use std::cell::RefCell;
+
+use crate::interner::SymbolData;
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of strings each thread caches. Must be a power of two.
+const CACHE_SIZE: usize = 64;
+
+/// Bumped whenever the interner is cleared, so that every thread's cache knows to forget the
+/// entries it holds without having to look at them.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+struct Cache {
+    /// The value of `GENERATION` when the entries were cached.
+    generation: usize,
+    entries: [Option<&'static SymbolData>; CACHE_SIZE],
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = const {
+        RefCell::new(Cache {
+            generation: 0,
+            entries: [None; CACHE_SIZE],
+        })
+    };
+}
+
+/// Looks up a string in this thread's cache, returning the interned copy on a hit.
+pub(crate) fn get(s: &str) -> Option<&'static SymbolData> {
+    CACHE
+        .try_with(|cache| {
+            let cache = cache.borrow();
+            if cache.generation != GENERATION.load(Ordering::Relaxed) {
+                return None;
+            }
+            cache.entries[slot(s)].filter(|t| t.as_str() == s)
+        })
+        .ok()
+        .flatten()
+}
+
+/// Records an interned string in this thread's cache.
+pub(crate) fn put(data: &'static SymbolData) {
+    let _ = CACHE.try_with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if cache.generation != generation {
+            *cache = Cache {
+                generation,
+                entries: [None; CACHE_SIZE],
+            };
+        }
+        cache.entries[slot(data.as_str())] = Some(data);
+    });
+}
+
+/// Makes every thread's cache forget what it holds.
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Picks the slot for a string. This has to be much cheaper than hashing the whole string, or the
+/// cache would be slower than the interner's lock-free lookup.
+fn slot(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let first = bytes.first().map_or(0, |&b| b as usize);
+    let last = bytes.last().map_or(0, |&b| b as usize);
+    (bytes.len().wrapping_mul(31) ^ first ^ (last << 3)) & (CACHE_SIZE - 1)
+}
@@ -0,0 +1,116 @@
+//! Case-insensitive symbols, which keep the spelling they were first interned with.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use crate::sync::Mutex;
+use crate::{Interner, LocalSymbol, Symbol};
+
+/// The lowercase versions of the strings, which decide which case-insensitive symbol is which.
+static FOLDED: Interner = Interner::new();
+
+/// The spellings the strings were first interned with. Each has the same id as its lowercase
+/// version in `FOLDED`.
+static SPELLINGS: Interner = Interner::new();
+
+/// Held while interning a new string into both interners, so that their ids stay in step.
+static NEW: Mutex<()> = Mutex::new(());
+
+/// A symbol that is equal to another whenever their strings are equal ignoring case, like the
+/// identifiers of SQL and the header names of HTTP.
+///
+/// Each one keeps the spelling it was first interned with, which [`as_str`](CiSymbol::as_str)
+/// returns and [`Display`] shows, however it is spelled later. Strings are compared by their
+/// lowercase versions, as made by [`str::to_lowercase`].
+///
+/// Case-insensitive symbols have their own interner, like [`Keyword`](crate::Keyword)s. Interning
+/// a string that has been interned before, in any spelling, doesn't lock; it costs making the
+/// lowercase version, which only allocates if the string isn't lowercase already, and a lookup.
+///
+/// ```
+/// # use symbol::CiSymbol;
+/// let first = CiSymbol::from("Content-Type");
+/// let later = CiSymbol::from("CONTENT-TYPE");
+/// assert_eq!(first, later);
+/// assert_eq!(later.as_str(), "Content-Type");
+/// assert_eq!(later.folded(), "content-type");
+/// assert_ne!(first, CiSymbol::from("Content-Length"));
+/// ```
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CiSymbol(LocalSymbol<'static>);
+
+impl CiSymbol {
+    /// Interns a string, keeping its spelling if no other spelling of it has been interned.
+    pub fn new(s: &str) -> CiSymbol {
+        let folded = fold(s);
+        if let Some(sym) = FOLDED.try_get(&folded) {
+            return CiSymbol(sym);
+        }
+
+        let _guard = NEW.lock();
+        if let Some(sym) = FOLDED.try_get(&folded) {
+            return CiSymbol(sym);
+        }
+        let sym = FOLDED.intern(&folded);
+        // No spelling of this string was interned before, so this is a new entry too.
+        let spelling = SPELLINGS.intern(s);
+        debug_assert_eq!(sym.id(), spelling.id());
+        CiSymbol(sym)
+    }
+
+    /// Returns the spelling the string was first interned with.
+    pub fn as_str(self) -> &'static str {
+        SPELLINGS
+            .get_by_id(self.0.id())
+            .expect("a case-insensitive symbol has no spelling")
+            .as_str()
+    }
+
+    /// Returns the lowercase version of the string, which is the same for every spelling.
+    pub fn folded(self) -> &'static str {
+        self.0.as_str()
+    }
+
+    /// Returns the symbol for the spelling the string was first interned with.
+    pub fn to_symbol(self) -> Symbol {
+        Symbol::from(self.as_str())
+    }
+}
+
+/// Returns the lowercase version of a string, without allocating if it is already lowercase.
+fn fold(s: &str) -> Cow<'_, str> {
+    // As in `Symbol::to_lowercase_sym`, a string of characters that are each their own lowercase
+    // is its own lowercase version.
+    if s.chars().all(|c| c.to_lowercase().eq([c])) {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(s.to_lowercase())
+    }
+}
+
+impl Debug for CiSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(self.as_str(), fmt)
+    }
+}
+
+impl Display for CiSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.pad(self.as_str())
+    }
+}
+
+impl<'a> From<&'a str> for CiSymbol {
+    fn from(s: &'a str) -> CiSymbol {
+        CiSymbol::new(s)
+    }
+}
+
+impl From<Symbol> for CiSymbol {
+    fn from(sym: Symbol) -> CiSymbol {
+        CiSymbol::new(sym.as_str())
+    }
+}
@@ -0,0 +1,71 @@
+//! Formatting strings to intern without allocating for the ones that are short.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use std::fmt::{Arguments, Result as FmtResult, Write};
+use std::str;
+
+/// How many bytes of formatted output fit on the stack before it is moved into a `String`.
+const INLINE: usize = 128;
+
+/// The output of formatting: on the stack until it outgrows [`INLINE`] bytes, then on the heap.
+pub(crate) enum Formatted {
+    Inline { buf: [u8; INLINE], len: usize },
+    Heap(String),
+}
+
+impl Formatted {
+    /// Returns an empty buffer, for writing to.
+    pub fn empty() -> Formatted {
+        Formatted::Inline {
+            buf: [0; INLINE],
+            len: 0,
+        }
+    }
+
+    /// Formats the arguments.
+    pub fn new(args: Arguments) -> Formatted {
+        let mut out = Formatted::empty();
+        // Writing to either variant can't fail, so an error can only come from a `Display` impl
+        // that is broken the same way it would be for `format!`, which panics.
+        out.write_fmt(args)
+            .expect("a formatting trait implementation returned an error");
+        out
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            // SAFETY: Only whole `&str`s are ever copied into the buffer.
+            Formatted::Inline { buf, len } => unsafe { str::from_utf8_unchecked(&buf[..*len]) },
+            Formatted::Heap(s) => s,
+        }
+    }
+
+    /// Appends a string.
+    pub fn push_str(&mut self, s: &str) {
+        match self {
+            Formatted::Inline { buf, len } if *len + s.len() <= INLINE => {
+                buf[*len..*len + s.len()].copy_from_slice(s.as_bytes());
+                *len += s.len();
+            }
+            Formatted::Inline { .. } => {
+                let mut heap = String::with_capacity(self.as_str().len() + s.len());
+                heap.push_str(self.as_str());
+                heap.push_str(s);
+                *self = Formatted::Heap(heap);
+            }
+            Formatted::Heap(heap) => heap.push_str(s),
+        }
+    }
+}
+
+impl Write for Formatted {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.push_str(s);
+        Ok(())
+    }
+}
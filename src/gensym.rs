@@ -0,0 +1,67 @@
+//! Per-thread blocks of gensym numbers, so that threads making many gensyms at once don't all
+//! contend for the interner's counter.
+
+use std::cell::Cell;
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of gensym numbers a thread takes from the counter at a time.
+const BLOCK_SIZE: usize = 64;
+
+/// Bumped whenever a gensym counter is set or an interner is dropped, so that every thread knows
+/// to give back the block it holds.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy)]
+struct Block {
+    /// The address of the counter the block was taken from.
+    counter: usize,
+    /// The value of `GENERATION` when the block was taken.
+    generation: usize,
+    next: usize,
+    end: usize,
+}
+
+thread_local! {
+    static BLOCK: Cell<Block> = const {
+        Cell::new(Block {
+            counter: 0,
+            generation: 0,
+            next: 0,
+            end: 0,
+        })
+    };
+}
+
+/// Returns the next number from this thread's block of `counter`, taking a new block if need be.
+pub(crate) fn next(counter: &AtomicUsize) -> usize {
+    BLOCK
+        .try_with(|block| {
+            let mut current = block.get();
+            let addr = counter as *const AtomicUsize as usize;
+            let generation = GENERATION.load(Ordering::Relaxed);
+            if current.counter != addr
+                || current.generation != generation
+                || current.next == current.end
+            {
+                let start = counter.fetch_add(BLOCK_SIZE, Ordering::Relaxed);
+                current = Block {
+                    counter: addr,
+                    generation,
+                    next: start,
+                    end: start + BLOCK_SIZE,
+                };
+            }
+            block.set(Block {
+                next: current.next + 1,
+                ..current
+            });
+            current.next
+        })
+        .unwrap_or_else(|_| counter.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Makes every thread take a new block the next time it makes a gensym.
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
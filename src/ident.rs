@@ -0,0 +1,151 @@
+//! Identifiers with the hygiene information a macro expander needs.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use foldhash::fast::FixedState;
+use hashbrown::HashMap;
+
+use crate::sync::atomic::{AtomicU32, Ordering};
+use crate::sync::Mutex;
+use crate::Symbol;
+
+/// Every syntax context other than the root, as its parent and the mark applied to it, along with
+/// a map back from those to the context, so that applying a mark twice gives the same context.
+struct Contexts {
+    parents: Vec<(SyntaxContext, Mark)>,
+    children: HashMap<(SyntaxContext, Mark), SyntaxContext, FixedState>,
+}
+
+static CONTEXTS: Mutex<Contexts> = Mutex::new(Contexts {
+    parents: Vec::new(),
+    children: HashMap::with_hasher(FixedState::with_seed(0)),
+});
+
+/// The last mark handed out.
+static LAST_MARK: AtomicU32 = AtomicU32::new(0);
+
+/// A mark a macro expander applies to the identifiers one expansion introduces, so that they can
+/// be told apart from identifiers with the same name from elsewhere.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Mark(u32);
+
+impl Mark {
+    /// Returns a mark that is different from every other mark.
+    pub fn fresh() -> Mark {
+        Mark(LAST_MARK.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+/// The sequence of marks that have been applied to an identifier, stored as a small number.
+///
+/// Contexts are shared: applying the same marks in the same order always gives the same context,
+/// so comparing two contexts is just comparing numbers.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SyntaxContext(u32);
+
+impl SyntaxContext {
+    /// The context of identifiers no mark has been applied to.
+    pub const ROOT: SyntaxContext = SyntaxContext(0);
+
+    /// Returns the context with one more mark applied.
+    pub fn apply_mark(self, mark: Mark) -> SyntaxContext {
+        let mut contexts = CONTEXTS.lock();
+        if let Some(&child) = contexts.children.get(&(self, mark)) {
+            return child;
+        }
+        contexts.parents.push((self, mark));
+        let child = SyntaxContext(contexts.parents.len() as u32);
+        contexts.children.insert((self, mark), child);
+        child
+    }
+
+    /// Returns the mark applied last, and the context from before it was applied, or `None` for
+    /// the root context.
+    pub fn outer_mark(self) -> Option<(SyntaxContext, Mark)> {
+        let index = self.0.checked_sub(1)?;
+        Some(CONTEXTS.lock().parents[index as usize])
+    }
+}
+
+/// A symbol paired with the syntax context it appeared in.
+///
+/// Two identifiers are only equal if both their names and their contexts are, so an identifier
+/// introduced by a macro expansion doesn't capture or shadow one with the same name from the
+/// macro's caller. [`same_name`](Ident::same_name) compares just the names.
+///
+/// ```
+/// # use symbol::{Ident, Mark, Symbol};
+/// let x = Ident::from(Symbol::from("x"));
+/// let mark = Mark::fresh();
+/// let hygienic = x.apply_mark(mark);
+/// assert_ne!(hygienic, x);
+/// assert!(hygienic.same_name(x));
+/// assert_eq!(hygienic.apply_mark(mark), x.apply_mark(mark).apply_mark(mark));
+/// assert_eq!(hygienic.strip_marks(), x);
+/// ```
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Ident {
+    name: Symbol,
+    ctxt: SyntaxContext,
+}
+
+impl Ident {
+    /// Pairs a name with a context.
+    pub fn new(name: Symbol, ctxt: SyntaxContext) -> Ident {
+        Ident { name, ctxt }
+    }
+
+    /// Retrieves the name.
+    pub fn name(self) -> Symbol {
+        self.name
+    }
+
+    /// Retrieves the context.
+    pub fn ctxt(self) -> SyntaxContext {
+        self.ctxt
+    }
+
+    /// Returns the identifier with one more mark applied to its context.
+    pub fn apply_mark(self, mark: Mark) -> Ident {
+        Ident::new(self.name, self.ctxt.apply_mark(mark))
+    }
+
+    /// Returns the identifier in the root context.
+    pub fn strip_marks(self) -> Ident {
+        Ident::new(self.name, SyntaxContext::ROOT)
+    }
+
+    /// Returns whether two identifiers have the same name, whatever their contexts.
+    pub fn same_name(self, other: Ident) -> bool {
+        self.name == other.name
+    }
+}
+
+/// Shows the context after the name, unless it is the root.
+impl Debug for Ident {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.name, fmt)?;
+        if self.ctxt != SyntaxContext::ROOT {
+            write!(fmt, "#{}", self.ctxt.0)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Ident {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.name, fmt)
+    }
+}
+
+impl From<Symbol> for Ident {
+    fn from(name: Symbol) -> Ident {
+        Ident::new(name, SyntaxContext::ROOT)
+    }
+}
@@ -0,0 +1,71 @@
+//! Interning the strings from an iterator as they are needed.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::{self, Vec};
+#[cfg(feature = "std")]
+use std::vec;
+
+use crate::Symbol;
+
+/// The number of strings interned at a time.
+const BATCH: usize = 256;
+
+/// Adds [`interned`](InternIter::interned) to iterators of strings.
+///
+/// ```
+/// use symbol::InternIter;
+///
+/// let syms = "let x = x + 1".split(' ').interned().collect::<Vec<_>>();
+/// assert_eq!(syms, ["let", "x", "=", "x", "+", "1"]);
+/// assert_eq!(syms[1].addr(), syms[3].addr());
+/// ```
+pub trait InternIter: Iterator + Sized
+where
+    Self::Item: AsRef<str>,
+{
+    /// Interns the strings, yielding their symbols in order.
+    ///
+    /// The strings are interned a batch at a time, as with [`Symbol::intern_all`], so each of the
+    /// interner's locks is taken at most once per batch. Each batch is taken from the iterator when
+    /// its first symbol is needed.
+    fn interned(self) -> Interned<Self> {
+        Interned {
+            iter: self,
+            batch: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<I: Iterator> InternIter for I where I::Item: AsRef<str> {}
+
+/// An iterator over the symbols for the strings of another iterator, from
+/// [`InternIter::interned`].
+pub struct Interned<I> {
+    iter: I,
+    batch: vec::IntoIter<Symbol>,
+}
+
+impl<I: Iterator> Iterator for Interned<I>
+where
+    I::Item: AsRef<str>,
+{
+    type Item = Symbol;
+
+    fn next(&mut self) -> Option<Symbol> {
+        if let Some(sym) = self.batch.next() {
+            return Some(sym);
+        }
+        let strs = self.iter.by_ref().take(BATCH).collect::<Vec<_>>();
+        self.batch = Symbol::intern_all(strs).into_iter();
+        self.batch.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let buffered = self.batch.len();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
@@ -0,0 +1,1326 @@
+//! Tables of interned strings, including the global one behind `Symbol`.
+//!
+//! Strings are never removed from the table, so it can be read without locking: each shard keeps
+//! an open-addressed table of pointers to entries, published with atomic stores. Writers serialize
+//! on a per-shard lock, and when a table fills up it is copied into a bigger one rather than being
+//! resized in place. Superseded tables are kept alive, since a reader may still be probing them.
+//!
+//! Each entry is also given a dense id when it is inserted, and recorded in a table that maps ids
+//! back to entries.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::mem::{size_of, size_of_val};
+#[cfg(feature = "debug-intern-sites")]
+use std::panic::Location;
+use std::ptr::{null_mut, slice_from_raw_parts_mut};
+use std::sync::Arc;
+
+use foldhash::fast::FixedState;
+use hashbrown::HashMap;
+
+use crate::arena::{Arena, Mark, Str};
+#[cfg(feature = "std")]
+use crate::gensym;
+use crate::local::LocalSymbol;
+#[cfg(feature = "metrics")]
+use crate::metrics::{InternerMetrics, Metrics};
+#[cfg(feature = "props")]
+use crate::props::Props;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicUsize, Ordering};
+use crate::sync::{Mutex, MutexGuard};
+use crate::InternError;
+
+/// The number of shards the table is split into. Must be a power of two.
+const SHARD_COUNT: usize = 32;
+
+/// The number of slots in a shard's first table. Must be a power of two.
+const INITIAL_CAPACITY: usize = 16;
+
+/// A set of interned strings, for interning strings separately from the global interner.
+///
+/// [`Symbol`](crate::Symbol)s come from a global interner, which never frees its strings. An
+/// `Interner` of one's own frees everything it interned when it is dropped, and its symbols borrow
+/// from it to make sure none outlive it. This suits strings that only matter for a while, such as
+/// those from one file or one request. Interning works the same way as with the global interner:
+/// looking up a string that is already present never locks.
+///
+/// ```
+/// # use symbol::Interner;
+/// let interner = Interner::new();
+/// let a = interner.intern("local");
+/// let b = interner.intern("local");
+/// assert_eq!(a, b);
+/// assert_eq!(a, "local");
+/// assert_eq!(interner.len(), 1);
+/// assert!(interner.try_get("other").is_none());
+/// ```
+// The interner's internals hand out `&'static` references to its entries. For the global interner
+// that is the truth, but other interners must not let them escape without tying them to `&self`.
+pub struct Interner {
+    hasher: FixedState,
+    shards: [Shard; SHARD_COUNT],
+    ids: IdTable,
+    frozen: AtomicBool,
+
+    /// The number the next gensym tries to use.
+    gensym: AtomicUsize,
+
+    /// For generated names other than plain gensyms, the number the next name with each stem (the
+    /// part before the number) tries to use.
+    stems: Mutex<HashMap<Box<str>, usize, FixedState>>,
+
+    /// The total length of the interned strings, kept here so that the limit on it can be checked
+    /// without locking every shard.
+    string_bytes: AtomicUsize,
+
+    /// The limits from [`Limits`], with `usize::MAX` for none.
+    max_len: AtomicUsize,
+    max_bytes: AtomicUsize,
+
+    /// The callback for new entries, if there is one. It is boxed again so that it is a thin
+    /// pointer.
+    observer: AtomicPtr<Observer>,
+
+    /// Bumped whenever entries are removed, so that symbols cached elsewhere can be recognized as
+    /// possibly freed.
+    epoch: AtomicUsize,
+
+    /// Caches of symbols that have been replaced since entries were last removed. Another thread
+    /// may still be reading one until then, so they're freed along with the next removed entries.
+    retired: Mutex<Vec<Box<dyn Send>>>,
+
+    #[cfg(feature = "props")]
+    pub(crate) props: Props,
+}
+
+/// A callback for new entries, from [`set_observer`](Interner::set_observer).
+pub(crate) type Observer = Box<dyn Fn(&'static SymbolData) + Send + Sync>;
+
+impl Interner {
+    /// Creates an empty interner.
+    ///
+    /// This doesn't allocate, and can be used to initialize a `static`.
+    pub const fn new() -> Interner {
+        // Array repetition needs a constant to copy, even though a shard isn't `Copy`.
+        #[allow(clippy::declare_interior_mutable_const)]
+        const SHARD: Shard = Shard::new();
+        Interner {
+            // This is the same as `FixedState::default()`.
+            hasher: FixedState::with_seed(0),
+            shards: [SHARD; SHARD_COUNT],
+            ids: IdTable::new(),
+            frozen: AtomicBool::new(false),
+            gensym: AtomicUsize::new(0),
+            stems: Mutex::new(HashMap::with_hasher(FixedState::with_seed(0))),
+            string_bytes: AtomicUsize::new(0),
+            max_len: AtomicUsize::new(usize::MAX),
+            max_bytes: AtomicUsize::new(usize::MAX),
+            observer: AtomicPtr::new(null_mut()),
+            epoch: AtomicUsize::new(0),
+            retired: Mutex::new(Vec::new()),
+            #[cfg(feature = "props")]
+            props: Props::new(),
+        }
+    }
+
+    /// Interns a string, copying it into the interner if it is not already present.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn intern(&self, s: &str) -> LocalSymbol<'_> {
+        let hash = self.hash(s);
+        let data = match self.entry(hash, s) {
+            Entry::Occupied(data) => data,
+            Entry::Vacant(entry) => entry.insert(s),
+        };
+        LocalSymbol::new(data)
+    }
+
+    /// Interns a string, failing instead if the interner is [frozen](Interner::freeze) or the
+    /// string is over its [limits](Interner::set_limits).
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn try_intern(&self, s: &str) -> Result<LocalSymbol<'_>, InternError> {
+        self.try_intern_data(s).map(LocalSymbol::new)
+    }
+
+    /// Returns the symbol for a string if it has been interned, without interning it otherwise.
+    pub fn try_get(&self, s: &str) -> Option<LocalSymbol<'_>> {
+        self.get(self.hash(s), s).map(LocalSymbol::new)
+    }
+
+    /// Returns the number of strings that have been interned.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.state.lock().len).sum()
+    }
+
+    /// Returns whether nothing has been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sets limits on what [`try_intern`](Interner::try_intern) will intern.
+    ///
+    /// Lowering a limit below what has already been interned doesn't remove anything; it just
+    /// makes `try_intern` fail for new strings.
+    pub fn set_limits(&self, limits: Limits) {
+        let max_len = limits.max_len.unwrap_or(usize::MAX);
+        let max_bytes = limits.max_bytes.unwrap_or(usize::MAX);
+        self.max_len.store(max_len, Ordering::Relaxed);
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the limits set by [`set_limits`](Interner::set_limits).
+    pub fn limits(&self) -> Limits {
+        let limit =
+            |max: &AtomicUsize| Some(max.load(Ordering::Relaxed)).filter(|&max| max != usize::MAX);
+        Limits {
+            max_len: limit(&self.max_len),
+            max_bytes: limit(&self.max_bytes),
+        }
+    }
+
+    /// Freezes the interner, so that [`try_intern`](Interner::try_intern) fails for strings that
+    /// were not already interned.
+    pub fn freeze(&self) {
+        self.frozen.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the interner has been frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    /// Interns a string unless the interner is frozen or the string is over its limits.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub(crate) fn try_intern_data(&self, s: &str) -> Result<&'static SymbolData, InternError> {
+        let hash = self.hash(s);
+        match self.entry(hash, s) {
+            Entry::Occupied(data) => Ok(data),
+            Entry::Vacant(entry) => entry.try_insert(s),
+        }
+    }
+
+    /// Hashes a string.
+    pub(crate) fn hash(&self, s: &str) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        s.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Looks up a string without locking or inserting it.
+    pub(crate) fn get(&self, hash: u64, s: &str) -> Option<&'static SymbolData> {
+        let shard = self.shard(hash);
+        let found = shard.find(hash, s);
+        #[cfg(feature = "metrics")]
+        match found {
+            Some(_) => shard.metrics.hit(),
+            None => shard.metrics.miss(),
+        }
+        found
+    }
+
+    /// Looks up a string, returning either the interned copy or a handle for inserting one. The
+    /// lock is only taken if the string is not already present, and is held by the handle.
+    pub(crate) fn entry(&self, hash: u64, s: &str) -> Entry<'_> {
+        self.entry_by(hash, s, |shard| Some(self.lock(shard)))
+            .expect("waiting for a lock can't fail")
+    }
+
+    /// Like `entry`, but returns `None` instead of waiting if the shard's lock is held.
+    pub(crate) fn try_entry(&self, hash: u64, s: &str) -> Option<Entry<'_>> {
+        self.entry_by(hash, s, |shard| {
+            let state = shard.state.try_lock()?;
+            Some(LockedShard {
+                shard,
+                interner: self,
+                state,
+            })
+        })
+    }
+
+    /// Looks up a string, taking the lock with `lock` if it is not already present.
+    fn entry_by<'a>(
+        &'a self,
+        hash: u64,
+        s: &str,
+        lock: impl FnOnce(&'a Shard) -> Option<LockedShard<'a>>,
+    ) -> Option<Entry<'a>> {
+        let shard = self.shard(hash);
+        if let Some(entry) = shard.find(hash, s) {
+            #[cfg(feature = "metrics")]
+            shard.metrics.hit();
+            return Some(Entry::Occupied(entry));
+        }
+
+        let locked = lock(shard)?;
+        Some(match locked.find(hash, s) {
+            Some(entry) => {
+                #[cfg(feature = "metrics")]
+                shard.metrics.hit();
+                Entry::Occupied(entry)
+            }
+            None => {
+                #[cfg(feature = "metrics")]
+                shard.metrics.miss();
+                Entry::Vacant(VacantEntry {
+                    shard: locked,
+                    hash,
+                })
+            }
+        })
+    }
+
+    /// Interns a batch of strings, locking each shard at most once.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub(crate) fn intern_all(&self, strs: &[&str]) -> Vec<&'static SymbolData> {
+        self.intern_all_by(strs, Str::Borrowed)
+    }
+
+    /// As `intern_all`, keeping references to the strings instead of copying them.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub(crate) fn intern_all_static(&self, strs: &[&'static str]) -> Vec<&'static SymbolData> {
+        self.intern_all_by(strs, Str::Static)
+    }
+
+    /// Interns a batch of strings, storing the new ones as `store` says.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn intern_all_by<'s>(
+        &self,
+        strs: &[&'s str],
+        store: impl Fn(&'s str) -> Str<'s>,
+    ) -> Vec<&'static SymbolData> {
+        let hashes = strs.iter().map(|s| self.hash(s)).collect::<Vec<_>>();
+        let mut out = strs
+            .iter()
+            .zip(&hashes)
+            .map(|(s, &hash)| {
+                let shard = self.shard(hash);
+                let found = shard.find(hash, s);
+                #[cfg(feature = "metrics")]
+                if found.is_some() {
+                    shard.metrics.hit();
+                }
+                found
+            })
+            .collect::<Vec<_>>();
+
+        // Lock the shards in order, so that two batches can't deadlock each other.
+        let mut misses = (0..strs.len())
+            .filter(|&i| out[i].is_none())
+            .collect::<Vec<_>>();
+        misses.sort_unstable_by_key(|&i| shard_index(hashes[i]));
+        let mut locked: Option<(usize, LockedShard)> = None;
+        let mut inserted = Vec::new();
+        for i in misses {
+            let index = shard_index(hashes[i]);
+            let shard = match &mut locked {
+                Some((j, shard)) if *j == index => shard,
+                _ => {
+                    locked = None;
+                    &mut locked.insert((index, self.lock(&self.shards[index]))).1
+                }
+            };
+            let (entry, new) = shard.intern(hashes[i], store(strs[i]));
+            if new {
+                inserted.push(entry);
+            }
+            out[i] = Some(entry);
+        }
+        drop(locked);
+        for entry in inserted {
+            self.observe(entry);
+        }
+
+        out.into_iter().map(Option::unwrap).collect()
+    }
+
+    /// Makes room for at least `additional` more strings without the tables having to grow.
+    pub fn reserve(&self, additional: usize) {
+        // Strings spread evenly over the shards, so give them an equal share, rounded up.
+        let per_shard = (additional + SHARD_COUNT - 1) / SHARD_COUNT;
+        for shard in &self.shards {
+            let state = shard.state.lock();
+            shard.reserve(state.len, per_shard);
+        }
+    }
+
+    /// Shrinks the tables as much as possible, and frees the tables they replaced when they grew.
+    pub fn shrink_to_fit(&mut self) {
+        // SAFETY: Nothing else can be using the interner while we have a unique reference to it.
+        unsafe { self.shrink_tables() }
+    }
+
+    /// As `shrink_to_fit`, for the global interner.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may use the interner until this returns.
+    pub(crate) unsafe fn shrink_tables(&self) {
+        for shard in &self.shards {
+            let state = shard.state.lock();
+            shard.shrink_to_fit(state.len);
+        }
+    }
+
+    /// Returns how many strings have been interned, and how much memory they are using.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        for shard in &self.shards {
+            let state = shard.state.lock();
+            stats.symbols += state.len + state.uninterned.len();
+            stats.string_bytes += state.string_bytes;
+            stats.arena_bytes += state.arena.allocated();
+
+            let mut table = shard.table();
+            while let Some(t) = table {
+                stats.table_bytes += t.size();
+                table = t.prev();
+            }
+        }
+        stats.table_bytes += self.ids.size();
+        stats
+    }
+
+    /// Returns a snapshot of the interner's counters.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn metrics(&self) -> InternerMetrics {
+        let mut snapshot = InternerMetrics::default();
+        for shard in &self.shards {
+            shard.metrics.add_to(&mut snapshot);
+        }
+        snapshot
+    }
+
+    /// Returns the entries that had been given ids when this was called, in id order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &'static SymbolData> + '_ {
+        (0..self.ids.len()).filter_map(move |id| self.ids.get(id))
+    }
+
+    /// Looks up an entry by its id.
+    pub(crate) fn get_by_id(&self, id: u32) -> Option<&'static SymbolData> {
+        self.ids.get(id)
+    }
+
+    /// Removes and frees every entry.
+    ///
+    /// # Safety
+    ///
+    /// No entry may be used afterwards, and no other thread may use the interner until this
+    /// returns.
+    pub(crate) unsafe fn clear(&self) {
+        for shard in &self.shards {
+            let mut state = shard.state.lock();
+            free_tables(shard.table.swap(null_mut(), Ordering::Acquire));
+            state.arena.clear();
+            *state = ShardState::default();
+        }
+        self.ids.clear();
+        self.string_bytes.store(0, Ordering::Relaxed);
+        self.removed();
+        #[cfg(feature = "props")]
+        self.props.retain(|_| false);
+    }
+
+    /// Returns the entry for every id handed out so far, in id order, or `None` for ids whose
+    /// entries have been removed.
+    ///
+    /// Unlike [`iter`](Interner::iter), this locks every shard while it runs, so that no id is
+    /// missing just because its entry was still being inserted.
+    pub(crate) fn entries(&self) -> Vec<Option<&'static SymbolData>> {
+        let _states = self
+            .shards
+            .iter()
+            .map(|shard| shard.state.lock())
+            .collect::<Vec<_>>();
+        // As in `checkpoint`, every id below `len` belongs to a fully inserted entry, unless the
+        // entry has been removed.
+        (0..self.ids.len()).map(|id| self.ids.get(id)).collect()
+    }
+
+    /// Returns the number of ids handed out so far.
+    pub(crate) fn id_count(&self) -> u32 {
+        self.ids.len()
+    }
+
+    /// Hands out an id without giving it an entry, returning it.
+    pub(crate) fn skip_id(&self) -> u32 {
+        self.ids.next_id()
+    }
+
+    /// Records which entries are present, so that the interner can be rolled back to this point.
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        // Ids are handed out with a shard locked, so once every shard is locked, every id below
+        // `next` belongs to an entry that is fully inserted.
+        let states = self
+            .shards
+            .iter()
+            .map(|shard| shard.state.lock())
+            .collect::<Vec<_>>();
+        Checkpoint {
+            ids: self.ids.len(),
+            marks: states.iter().map(|state| state.arena.mark()).collect(),
+        }
+    }
+
+    /// Removes and frees every entry inserted since a checkpoint was taken.
+    ///
+    /// # Safety
+    ///
+    /// The checkpoint must have come from this interner, and the interner must not have been
+    /// rolled back to an earlier checkpoint or cleared since. No entry inserted since the
+    /// checkpoint may be used afterwards, and no other thread may use the interner until this
+    /// returns.
+    pub(crate) unsafe fn rollback(&self, checkpoint: &Checkpoint) {
+        let mut string_bytes = 0;
+        for (shard, mark) in self.shards.iter().zip(&checkpoint.marks) {
+            let mut state = shard.state.lock();
+            shard.retain(&mut state, |entry| entry.id < checkpoint.ids);
+            state.arena.rollback(mark);
+            string_bytes += state.string_bytes;
+        }
+        self.ids.truncate(checkpoint.ids);
+        self.string_bytes.store(string_bytes, Ordering::Relaxed);
+        self.removed();
+        #[cfg(feature = "props")]
+        self.props.retain(|id| id < checkpoint.ids);
+    }
+
+    /// Removes every entry for which `keep` returns false, and frees the storage of those it can.
+    ///
+    /// Entries are stored next to each other, so storage is only freed once everything stored
+    /// with it is removed. The ids of removed entries are not handed out again.
+    ///
+    /// # Safety
+    ///
+    /// No removed entry may be used afterwards, no other thread may use the interner until this
+    /// returns, and the interner must not be rolled back to a checkpoint taken before this.
+    pub(crate) unsafe fn retain(&self, mut keep: impl FnMut(&SymbolData) -> bool) {
+        let mut string_bytes = 0;
+        for shard in &self.shards {
+            let mut state = shard.state.lock();
+            let kept = shard.retain(&mut state, |entry| {
+                let keep = keep(entry);
+                if !keep {
+                    self.ids.remove(entry.id);
+                }
+                keep
+            });
+
+            let mut live = kept
+                .iter()
+                .flat_map(|&entry| {
+                    [
+                        entry as *const SymbolData as usize,
+                        entry.s.as_ptr() as usize,
+                    ]
+                })
+                .collect::<Vec<_>>();
+            live.sort_unstable();
+            state.arena.retain(|start, end| {
+                let i = live.partition_point(|&addr| addr < start);
+                live.get(i).map_or(false, |&addr| addr < end)
+            });
+            string_bytes += state.string_bytes;
+        }
+        self.string_bytes.store(string_bytes, Ordering::Relaxed);
+        self.removed();
+        #[cfg(feature = "props")]
+        self.props.retain(|id| self.ids.get(id).is_some());
+    }
+
+    /// Returns a number that changes whenever entries are removed, including by dropping the
+    /// interner.
+    pub(crate) fn epoch(&self) -> usize {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    /// Keeps a replaced cache of symbols until it can't be in use any more.
+    pub(crate) fn retire(&self, cache: Box<dyn Send>) {
+        self.retired.lock().push(cache);
+    }
+
+    /// Records that entries have been removed, and frees the caches retired before then. Must be
+    /// called under the same conditions as the removal.
+    fn removed(&self) {
+        self.epoch.fetch_add(1, Ordering::Release);
+        self.retired.lock().clear();
+    }
+
+    /// Sets the callback for entries inserted from now on, replacing any there was.
+    pub(crate) fn set_observer(&self, observer: Option<Observer>) {
+        let new = observer.map_or(null_mut(), |observer| Box::into_raw(Box::new(observer)));
+        // The old observer may still be running on another thread, so it is leaked rather than
+        // freed.
+        self.observer.swap(new, Ordering::AcqRel);
+    }
+
+    /// Calls the observer, if there is one, for a new entry. No shard may be locked by this thread.
+    fn observe(&self, entry: &'static SymbolData) {
+        let observer = self.observer.load(Ordering::Acquire);
+        if !observer.is_null() {
+            // SAFETY: Observers are only freed when the interner is dropped.
+            unsafe { (*observer)(entry) }
+        }
+    }
+
+    /// Returns a number for a gensym, which is different each time.
+    pub(crate) fn next_gensym(&self) -> usize {
+        #[cfg(feature = "std")]
+        return gensym::next(&self.gensym);
+        #[cfg(not(feature = "std"))]
+        self.gensym.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Makes a symbol that no lookup will find, and that is only equal to itself.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub(crate) fn uninterned(&self, s: &str) -> &'static SymbolData {
+        let hash = self.hash(s);
+        self.lock(self.shard(hash))
+            .insert_uninterned(hash, Str::Borrowed(s), 0)
+    }
+
+    /// Like `uninterned`, marking the symbol as a gensym.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub(crate) fn gensym(&self, s: &str) -> &'static SymbolData {
+        let hash = self.hash(s);
+        self.lock(self.shard(hash))
+            .insert_uninterned(hash, Str::Borrowed(s), GENSYM)
+    }
+
+    /// Like `next_gensym`, for generated names that start with `stem`.
+    pub(crate) fn next_with_stem(&self, stem: &str) -> usize {
+        let mut stems = self.stems.lock();
+        let n = match stems.get_mut(stem) {
+            Some(n) => n,
+            None => stems.entry(stem.into()).or_insert(0),
+        };
+        *n += 1;
+        *n - 1
+    }
+
+    /// Returns the number the next gensym tries to use.
+    pub(crate) fn gensym_counter(&self) -> usize {
+        self.gensym.load(Ordering::Relaxed)
+    }
+
+    /// Makes the next gensym try `n`.
+    pub(crate) fn set_gensym(&self, n: usize) {
+        self.gensym.store(n, Ordering::Relaxed);
+        #[cfg(feature = "std")]
+        gensym::invalidate();
+    }
+
+    /// Makes generated names with every stem start from zero again.
+    pub(crate) fn reset_stems(&self) {
+        self.stems.lock().clear();
+    }
+
+    /// Makes sure gensyms don't try numbers below `n`.
+    pub(crate) fn advance_gensym(&self, n: usize) {
+        self.gensym.fetch_max(n, Ordering::Relaxed);
+        #[cfg(feature = "std")]
+        gensym::invalidate();
+    }
+
+    /// Returns the shard responsible for strings with the given hash.
+    fn shard(&self, hash: u64) -> &Shard {
+        &self.shards[shard_index(hash)]
+    }
+
+    fn lock<'a>(&'a self, shard: &'a Shard) -> LockedShard<'a> {
+        LockedShard {
+            shard,
+            interner: self,
+            state: shard.lock(),
+        }
+    }
+}
+
+impl Default for Interner {
+    fn default() -> Interner {
+        Interner::new()
+    }
+}
+
+impl Drop for Interner {
+    fn drop(&mut self) {
+        // SAFETY: Every symbol from this interner borrows it, so none are left.
+        unsafe { self.clear() }
+        let observer = *self.observer.get_mut();
+        if !observer.is_null() {
+            // SAFETY: Nothing can be calling the observer, since the interner is borrowed mutably.
+            drop(unsafe { Box::from_raw(observer) });
+        }
+        // Another interner could be put where this one was, and mustn't use its blocks.
+        #[cfg(feature = "std")]
+        gensym::invalidate();
+    }
+}
+
+/// Returns the index of the shard responsible for strings with the given hash.
+fn shard_index(hash: u64) -> usize {
+    // Tables pick a slot from the low bits, so take the shard index from the high ones.
+    (hash >> 32) as usize & (SHARD_COUNT - 1)
+}
+
+/// How many strings have been interned, and how much memory they are using.
+///
+/// Interned strings are never freed, so all of these only ever grow.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of interned strings.
+    pub symbols: usize,
+
+    /// The total length of the interned strings, in bytes.
+    pub string_bytes: usize,
+
+    /// The number of bytes allocated to hold the strings and the interner's record of each one.
+    /// This includes space at the end of the most recent chunks that has not been used yet.
+    pub arena_bytes: usize,
+
+    /// The number of bytes used by the tables for looking strings up by content and by id. This
+    /// includes tables that have been replaced by bigger ones, which are never freed.
+    pub table_bytes: usize,
+}
+
+/// Limits on what [`Interner::try_intern`] and [`Symbol::try_intern`](crate::Symbol::try_intern)
+/// will intern, for programs that intern strings from untrusted input. `None` means no limit.
+///
+/// Other ways of interning don't check the limits, but do count towards `max_bytes`.
+///
+/// The limits bound how much memory untrusted input can use, but not how long interning it takes.
+/// Strings are hashed with a fixed seed, so that their hashes are the same on every run, and anyone
+/// who knows the seed can pick strings whose hashes collide. Each one interned makes looking up the
+/// others slower, in the interner and in tables keyed by [`SymbolHasher`](crate::SymbolHasher),
+/// which reuse the same hashes. Programs that take strings from an attacker should keep `max_bytes`
+/// small, or look them up in a table with a randomly seeded hasher before interning them.
+///
+/// ```
+/// # use symbol::{InternError, Interner, Limits};
+/// let interner = Interner::new();
+/// interner.set_limits(Limits {
+///     max_len: Some(8),
+///     max_bytes: Some(10),
+/// });
+/// assert!(interner.try_intern("short").is_ok());
+/// assert_eq!(interner.try_intern("much too long").unwrap_err(), InternError::TooLong);
+/// assert_eq!(interner.try_intern("another").unwrap_err(), InternError::LimitReached);
+/// assert!(interner.try_intern("short").is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Limits {
+    /// The length of the longest string that can be interned, in bytes.
+    pub max_len: Option<usize>,
+
+    /// The total length of all the interned strings, in bytes. Strings that would take the total
+    /// over this are not interned.
+    pub max_bytes: Option<usize>,
+}
+
+/// A point the interner can be rolled back to, from [`Interner::checkpoint`].
+pub(crate) struct Checkpoint {
+    /// The number of ids handed out when the checkpoint was taken.
+    ids: u32,
+
+    /// Each shard's arena's mark.
+    marks: Vec<Mark>,
+}
+
+/// The result of looking up a string with [`Interner::entry`].
+pub(crate) enum Entry<'a> {
+    /// The string was already interned.
+    Occupied(&'static SymbolData),
+
+    /// The string was not interned. The shard stays locked until this is dropped.
+    Vacant(VacantEntry<'a>),
+}
+
+/// A handle for inserting a string that was not found in the interner.
+pub(crate) struct VacantEntry<'a> {
+    shard: LockedShard<'a>,
+    hash: u64,
+}
+
+impl<'a> VacantEntry<'a> {
+    /// Inserts a copy of the string, which must be the one that was looked up.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn insert(mut self, s: &str) -> &'static SymbolData {
+        let entry = self.shard.insert(self.hash, Str::Borrowed(s), 0);
+        self.finish(entry)
+    }
+
+    /// Inserts the string, which must be the one that was looked up, taking ownership of its
+    /// buffer if it is long enough to need an allocation of its own.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn insert_owned(mut self, s: String) -> &'static SymbolData {
+        let entry = self.shard.insert(self.hash, Str::Owned(s), 0);
+        self.finish(entry)
+    }
+
+    /// As `insert_owned`, keeping a reference to the `Arc` instead.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn insert_shared(mut self, s: Arc<str>) -> &'static SymbolData {
+        let entry = self.shard.insert(self.hash, Str::Shared(s), 0);
+        self.finish(entry)
+    }
+
+    /// As `insert`, keeping a reference to the string instead of copying it.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn insert_static(mut self, s: &'static str) -> &'static SymbolData {
+        let entry = self.shard.insert(self.hash, Str::Static(s), 0);
+        self.finish(entry)
+    }
+
+    /// Inserts a copy of the string, unless the interner is frozen or the string is over its
+    /// limits.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn try_insert(mut self, s: &str) -> Result<&'static SymbolData, InternError> {
+        let interner = self.shard.interner;
+        if interner.is_frozen() {
+            return Err(InternError::Frozen);
+        }
+        if s.len() > interner.max_len.load(Ordering::Relaxed) {
+            return Err(InternError::TooLong);
+        }
+        let max_bytes = interner.max_bytes.load(Ordering::Relaxed);
+        interner
+            .string_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bytes| {
+                bytes
+                    .checked_add(s.len())
+                    .filter(|&bytes| bytes <= max_bytes)
+            })
+            .map_err(|_| InternError::LimitReached)?;
+        let entry = self.shard.insert_counted(self.hash, Str::Borrowed(s), 0);
+        Ok(self.finish(entry))
+    }
+
+    /// Unlocks the shard, then tells the observer about the entry that was inserted.
+    fn finish(self, entry: &'static SymbolData) -> &'static SymbolData {
+        let interner = self.shard.interner;
+        drop(self);
+        interner.observe(entry);
+        entry
+    }
+}
+
+/// The header the interner keeps for each symbol.
+///
+/// Unless the string is very long, its bytes follow the header directly in the same chunk of the
+/// shard's arena, so that probing the table and then comparing the string touches one place.
+pub(crate) struct SymbolData {
+    hash: u64,
+    id: u32,
+    flags: u8,
+    s: &'static str,
+
+    /// Where the string was first interned.
+    #[cfg(feature = "debug-intern-sites")]
+    site: &'static Location<'static>,
+}
+
+/// The flag for symbols that aren't in the table.
+const UNINTERNED: u8 = 1;
+
+/// The flag for symbols made by gensym.
+const GENSYM: u8 = 2;
+
+impl SymbolData {
+    pub fn as_str(&self) -> &'static str {
+        self.s
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The hash of the string, computed when it was interned.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Where the string was first interned.
+    #[cfg(feature = "debug-intern-sites")]
+    pub fn site(&self) -> &'static Location<'static> {
+        self.site
+    }
+
+    pub fn is_gensym(&self) -> bool {
+        self.flags & GENSYM != 0
+    }
+
+    /// Whether the symbol can be found by looking up its string.
+    pub fn is_interned(&self) -> bool {
+        self.flags & UNINTERNED == 0
+    }
+}
+
+/// One shard of the interner.
+struct Shard {
+    /// The current table, or null if nothing has been inserted yet.
+    table: AtomicPtr<Table>,
+
+    /// State only writers use. Writers hold this lock.
+    state: Mutex<ShardState>,
+
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+}
+
+/// The parts of a shard only writers use.
+#[derive(Default)]
+struct ShardState {
+    /// The number of entries in the shard.
+    len: usize,
+
+    /// The total length of the shard's strings.
+    string_bytes: usize,
+
+    /// Storage for the shard's entries.
+    arena: Arena,
+
+    /// The entries stored in the arena that aren't in the table.
+    uninterned: Vec<&'static SymbolData>,
+}
+
+impl ShardState {
+    const fn new() -> ShardState {
+        ShardState {
+            len: 0,
+            string_bytes: 0,
+            arena: Arena::new(),
+            uninterned: Vec::new(),
+        }
+    }
+}
+
+impl Shard {
+    const fn new() -> Shard {
+        Shard {
+            table: AtomicPtr::new(null_mut()),
+            state: Mutex::new(ShardState::new()),
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::new(),
+        }
+    }
+
+    /// Takes the shard's lock, waiting for it if need be.
+    fn lock(&self) -> MutexGuard<'_, ShardState> {
+        #[cfg(feature = "metrics")]
+        match self.state.try_lock() {
+            Some(state) => return state,
+            None => self.metrics.contended(),
+        }
+        self.state.lock()
+    }
+
+    /// Removes the entries for which `keep` returns false from the table, returning the rest.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may use the shard until this returns. `state` must be the shard's.
+    unsafe fn retain(
+        &self,
+        state: &mut ShardState,
+        mut keep: impl FnMut(&SymbolData) -> bool,
+    ) -> Vec<&'static SymbolData> {
+        let mut kept = Vec::new();
+        if let Some(table) = self.table() {
+            kept.extend(
+                table
+                    .slots
+                    .iter()
+                    .filter_map(|slot| slot.swap(null_mut(), Ordering::Relaxed).as_ref())
+                    .filter(|entry| keep(entry)),
+            );
+            for &entry in &kept {
+                table.insert(entry, Ordering::Relaxed);
+            }
+        }
+        state.len = kept.len();
+        state.uninterned.retain(|entry| keep(entry));
+        kept.extend(&state.uninterned);
+        state.string_bytes = kept.iter().map(|entry| entry.s.len()).sum();
+        kept
+    }
+
+    /// Returns the current table.
+    fn table(&self) -> Option<&Table> {
+        // SAFETY: Tables are never freed once published.
+        unsafe { self.table.load(Ordering::Acquire).as_ref() }
+    }
+
+    fn find(&self, hash: u64, s: &str) -> Option<&'static SymbolData> {
+        self.table().and_then(|table| table.find(hash, s))
+    }
+
+    /// Makes sure the current table has room for `additional` more entries, replacing it with a
+    /// bigger one if needed. Requires the shard's lock; `len` is the number of entries.
+    fn reserve(&self, len: usize, additional: usize) -> &Table {
+        let needed = len + additional;
+        let old = self.table.load(Ordering::Acquire);
+        // SAFETY: Tables are never freed once published.
+        if let Some(table) = unsafe { old.as_ref() } {
+            if needed <= table.max_len() {
+                return table;
+            }
+        }
+
+        let table = Table::new(capacity_for(needed), old);
+        // SAFETY: As above.
+        if let Some(old) = unsafe { old.as_ref() } {
+            table.copy_from(old);
+        }
+
+        let table = Box::into_raw(Box::new(table));
+        self.table.store(table, Ordering::Release);
+        // SAFETY: We just allocated it, and tables are never freed once published.
+        unsafe { &*table }
+    }
+
+    /// Replaces the current table with the smallest one that can hold `len` entries, and frees
+    /// the superseded ones. Requires the shard's lock; `len` is the number of entries.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may use the shard until this returns.
+    unsafe fn shrink_to_fit(&self, len: usize) {
+        let old = self.table.swap(null_mut(), Ordering::Acquire);
+        if let Some(old) = old.as_ref().filter(|_| len > 0) {
+            let table = Table::new(capacity_for(len), null_mut());
+            table.copy_from(old);
+            self.table
+                .store(Box::into_raw(Box::new(table)), Ordering::Release);
+        }
+        free_tables(old);
+    }
+}
+
+/// Returns the number of slots a table needs to hold `len` entries.
+fn capacity_for(len: usize) -> usize {
+    let mut capacity = INITIAL_CAPACITY;
+    while len > capacity / 2 {
+        capacity *= 2;
+    }
+    capacity
+}
+
+/// Frees a table, and the tables it replaced.
+///
+/// # Safety
+///
+/// The table must not be published, and nobody may be using it.
+unsafe fn free_tables(mut table: *mut Table) {
+    while !table.is_null() {
+        table = Box::from_raw(table).prev.into_inner();
+    }
+}
+
+/// A shard whose lock is held.
+struct LockedShard<'a> {
+    interner: &'a Interner,
+    shard: &'a Shard,
+    state: MutexGuard<'a, ShardState>,
+}
+
+impl<'a> LockedShard<'a> {
+    fn find(&self, hash: u64, s: &str) -> Option<&'static SymbolData> {
+        self.shard.find(hash, s)
+    }
+
+    /// Interns a string, storing it if it is not already present, and returns whether it was
+    /// stored.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn intern(&mut self, hash: u64, s: Str) -> (&'static SymbolData, bool) {
+        match self.find(hash, s.as_str()) {
+            Some(entry) => {
+                #[cfg(feature = "metrics")]
+                self.shard.metrics.hit();
+                (entry, false)
+            }
+            None => {
+                #[cfg(feature = "metrics")]
+                self.shard.metrics.miss();
+                (self.insert(hash, s, 0), true)
+            }
+        }
+    }
+
+    /// Inserts a string, which must not already be present.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn insert(&mut self, hash: u64, s: Str, flags: u8) -> &'static SymbolData {
+        let bytes = &self.interner.string_bytes;
+        bytes.fetch_add(s.as_str().len(), Ordering::Relaxed);
+        self.insert_counted(hash, s, flags)
+    }
+
+    /// Inserts a string, which must not already be present, after its length has been added to
+    /// the interner's total.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn insert_counted(&mut self, hash: u64, s: Str, flags: u8) -> &'static SymbolData {
+        let ids = &self.interner.ids;
+        let state = &mut *self.state;
+        let table = self.shard.reserve(state.len, 1);
+        let id = ids.next_id();
+        let len = s.as_str().len();
+        #[cfg(feature = "debug-intern-sites")]
+        let site = Location::caller();
+        let entry = state.arena.alloc(s, |s| SymbolData {
+            hash,
+            id,
+            flags,
+            s,
+            #[cfg(feature = "debug-intern-sites")]
+            site,
+        });
+        // Publish the id first, so that anyone who finds the entry can look it up by id.
+        ids.publish(id, entry);
+        table.insert(entry, Ordering::Release);
+        state.len += 1;
+        state.string_bytes += len;
+        entry
+    }
+
+    /// Stores a string with an id of its own, but without putting it in the table, so that it is
+    /// never found by looking it up.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn insert_uninterned(&mut self, hash: u64, s: Str, flags: u8) -> &'static SymbolData {
+        let ids = &self.interner.ids;
+        let state = &mut *self.state;
+        let id = ids.next_id();
+        let len = s.as_str().len();
+        self.interner.string_bytes.fetch_add(len, Ordering::Relaxed);
+        #[cfg(feature = "debug-intern-sites")]
+        let site = Location::caller();
+        let entry = state.arena.alloc(s, |s| SymbolData {
+            hash,
+            id,
+            flags: flags | UNINTERNED,
+            s,
+            #[cfg(feature = "debug-intern-sites")]
+            site,
+        });
+        ids.publish(id, entry);
+        state.uninterned.push(entry);
+        state.string_bytes += len;
+        entry
+    }
+}
+
+/// An open-addressed, linearly probed table of entries.
+struct Table {
+    slots: Box<[AtomicPtr<SymbolData>]>,
+
+    /// The table this one replaced, kept so that readers still probing it stay valid.
+    prev: AtomicPtr<Table>,
+}
+
+impl Table {
+    fn new(capacity: usize, prev: *mut Table) -> Table {
+        Table {
+            slots: (0..capacity).map(|_| AtomicPtr::new(null_mut())).collect(),
+            prev: AtomicPtr::new(prev),
+        }
+    }
+
+    /// Returns the table this one replaced.
+    fn prev(&self) -> Option<&Table> {
+        // SAFETY: Tables are never freed once published.
+        unsafe { self.prev.load(Ordering::Relaxed).as_ref() }
+    }
+
+    /// Returns the number of bytes the table uses.
+    fn size(&self) -> usize {
+        size_of::<Table>() + size_of_val(&*self.slots)
+    }
+
+    /// The number of entries the table can hold while staying at most half full.
+    fn max_len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// Returns the slots to probe for the given hash, in order.
+    fn probe(&self, hash: u64) -> impl Iterator<Item = &AtomicPtr<SymbolData>> {
+        let start = hash as usize & (self.slots.len() - 1);
+        self.slots[start..].iter().chain(&self.slots[..start])
+    }
+
+    fn find(&self, hash: u64, s: &str) -> Option<&'static SymbolData> {
+        for slot in self.probe(hash) {
+            // SAFETY: Entries are never freed once published.
+            let entry = unsafe { slot.load(Ordering::Acquire).as_ref() }?;
+            if entry.hash == hash && entry.s == s {
+                return Some(entry);
+            }
+        }
+        None
+    }
+
+    /// Copies every entry from another table. Requires the shard's lock, and that this table is not
+    /// yet published.
+    fn copy_from(&self, other: &Table) {
+        for slot in other.slots.iter() {
+            // SAFETY: Entries are never freed once published.
+            if let Some(entry) = unsafe { slot.load(Ordering::Relaxed).as_ref() } {
+                self.insert(entry, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Stores an entry in the first free slot. Requires the shard's lock, and that the table is not
+    /// full.
+    fn insert(&self, entry: &'static SymbolData, order: Ordering) {
+        for slot in self.probe(entry.hash) {
+            if slot.load(Ordering::Relaxed).is_null() {
+                slot.store(entry as *const SymbolData as *mut SymbolData, order);
+                return;
+            }
+        }
+        unreachable!("inserted into a full table")
+    }
+}
+
+/// A map from ids to entries, which can be read without locking.
+///
+/// It is split into segments that double in size, so that it can grow without moving what is
+/// already in it: segment `k` holds ids `2^k - 1` through `2^(k + 1) - 2`.
+struct IdTable {
+    next: AtomicU32,
+    segments: [AtomicPtr<AtomicPtr<SymbolData>>; 32],
+}
+
+impl IdTable {
+    const fn new() -> IdTable {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const NULL: AtomicPtr<AtomicPtr<SymbolData>> = AtomicPtr::new(null_mut());
+        IdTable {
+            next: AtomicU32::new(0),
+            segments: [NULL; 32],
+        }
+    }
+
+    /// Hands out an unused id.
+    fn next_id(&self) -> u32 {
+        // u32::MAX itself is never handed out, so that `id + 1` can't overflow.
+        self.next
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| {
+                id.checked_add(1).filter(|&next| next != u32::MAX)
+            })
+            .expect("too many symbols")
+    }
+
+    /// Returns the number of ids handed out so far.
+    fn len(&self) -> u32 {
+        self.next.load(Ordering::Relaxed)
+    }
+
+    /// Splits an id into a segment index and an offset into that segment.
+    fn locate(id: u32) -> (usize, usize) {
+        let n = id + 1;
+        let segment = 31 - n.leading_zeros();
+        (segment as usize, (n - (1 << segment)) as usize)
+    }
+
+    fn get(&self, id: u32) -> Option<&'static SymbolData> {
+        // Ids that were never handed out, u32::MAX among them, have no entry.
+        if id >= self.len() {
+            return None;
+        }
+        let (segment, offset) = IdTable::locate(id);
+        let segment = self.segments[segment].load(Ordering::Acquire);
+        if segment.is_null() {
+            return None;
+        }
+        // SAFETY: Segments are never freed once published, and are big enough for the offset.
+        let slot = unsafe { &*segment.add(offset) };
+        // SAFETY: Entries are never freed once published.
+        unsafe { slot.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// Returns the number of bytes used by the segments allocated so far.
+    fn size(&self) -> usize {
+        (0..self.segments.len())
+            .filter(|&k| !self.segments[k].load(Ordering::Acquire).is_null())
+            .map(|k| (1 << k) * size_of::<AtomicPtr<SymbolData>>())
+            .sum()
+    }
+
+    /// Records the entry for an id.
+    fn publish(&self, id: u32, entry: &'static SymbolData) {
+        let (index, offset) = IdTable::locate(id);
+        let mut segment = self.segments[index].load(Ordering::Acquire);
+        if segment.is_null() {
+            segment = self.alloc_segment(index);
+        }
+        // SAFETY: As in `get`.
+        let slot = unsafe { &*segment.add(offset) };
+        slot.store(
+            entry as *const SymbolData as *mut SymbolData,
+            Ordering::Release,
+        );
+    }
+
+    /// Frees every segment, and starts handing out ids from zero again.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may use the table until this returns.
+    unsafe fn clear(&self) {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let segment = segment.swap(null_mut(), Ordering::Acquire);
+            if !segment.is_null() {
+                drop(Box::from_raw(slice_from_raw_parts_mut(segment, 1 << index)));
+            }
+        }
+        self.next.store(0, Ordering::Relaxed);
+    }
+
+    /// Forgets the entry for an id, without handing the id out again.
+    fn remove(&self, id: u32) {
+        let (segment, offset) = IdTable::locate(id);
+        let segment = self.segments[segment].load(Ordering::Acquire);
+        if !segment.is_null() {
+            // SAFETY: As in `get`.
+            unsafe { &*segment.add(offset) }.store(null_mut(), Ordering::Relaxed);
+        }
+    }
+
+    /// Forgets every id from `len` on, and starts handing them out again.
+    ///
+    /// # Safety
+    ///
+    /// No other thread may use the table until this returns.
+    unsafe fn truncate(&self, len: u32) {
+        for id in len..self.len() {
+            self.remove(id);
+        }
+        self.next.store(len, Ordering::Relaxed);
+    }
+
+    /// Allocates a segment, unless another thread gets there first.
+    fn alloc_segment(&self, index: usize) -> *mut AtomicPtr<SymbolData> {
+        let slots: Box<[AtomicPtr<SymbolData>]> = (0..1_usize << index)
+            .map(|_| AtomicPtr::new(null_mut()))
+            .collect();
+        let new = Box::into_raw(slots);
+        let result = self.segments[index].compare_exchange(
+            null_mut(),
+            new.cast(),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+        match result {
+            Ok(_) => new.cast(),
+            Err(winner) => {
+                // SAFETY: We allocated it above, and never shared it.
+                drop(unsafe { Box::from_raw(new) });
+                winner
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Giving a thread an interner of its own, in place of the global one.
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+#[cfg(feature = "thread-local-cache")]
+use crate::cache;
+use crate::interner::Interner;
+
+thread_local! {
+    static CURRENT: Cell<Option<&'static Interner>> = const { Cell::new(None) };
+}
+
+/// Returns the interner this thread is using instead of the global one, if any.
+pub(crate) fn current() -> Option<&'static Interner> {
+    CURRENT.try_with(Cell::get).ok().flatten()
+}
+
+/// Makes [`Symbol`](crate::Symbol)s on this thread come from a new, empty interner until the
+/// returned guard is dropped. Requires the `isolation` feature.
+///
+/// This is meant for tests, which `cargo test` runs in parallel threads of one process: a test
+/// that checks [`stats`](crate::stats) or the names [`gensym`](crate::Symbol::gensym) picks, or
+/// that calls [`unsafe_clear`](crate::unsafe_clear), can isolate itself from the others. Every
+/// function in this crate that works with the global interner works with the thread's interner
+/// instead, including freezing it.
+///
+/// Symbols from different interners are different symbols, even if their strings are equal, so
+/// symbols shouldn't be passed between isolated code and the rest of the program. Threads the
+/// isolated thread spawns use the global interner. The isolated interner is never freed, so
+/// symbols from it stay valid after the guard is dropped.
+///
+/// Guards can be nested: dropping one goes back to whichever interner the thread used before.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let _ = Symbol::from("outside");
+/// let guard = symbol::isolate();
+/// assert_eq!(symbol::stats().symbols, 0);
+/// assert!(!symbol::is_interned("outside"));
+/// assert_eq!(Symbol::gensym(), "G#0");
+/// drop(guard);
+/// assert!(symbol::is_interned("outside"));
+/// ```
+pub fn isolate() -> IsolationGuard {
+    let interner = Box::leak(Box::new(Interner::new()));
+    let prev = CURRENT.with(|current| current.replace(Some(interner)));
+    #[cfg(feature = "thread-local-cache")]
+    cache::invalidate();
+    IsolationGuard {
+        prev,
+        _not_send: PhantomData,
+    }
+}
+
+/// A guard that puts back the interner a thread used before [`isolate`] when dropped.
+#[must_use = "the thread goes back to its previous interner when the guard is dropped"]
+pub struct IsolationGuard {
+    prev: Option<&'static Interner>,
+
+    /// The guard changes a thread-local, so it has to be dropped on the same thread.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for IsolationGuard {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|current| current.set(self.prev));
+        #[cfg(feature = "thread-local-cache")]
+        cache::invalidate();
+    }
+}
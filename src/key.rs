@@ -0,0 +1,38 @@
+//! Opaque keys for symbols, for storing in side tables.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use crate::Symbol;
+
+/// An opaque key for a symbol, from [`Symbol::key`].
+///
+/// Keys are for side tables that refer to symbols without holding them, such as a cache keyed by
+/// name. Each symbol has its own key, which doesn't change while the program runs, and
+/// [`Symbol::from_key`] turns a key back into its symbol. They mean nothing to other processes:
+/// data that outlives the process should hold the symbols' strings, or ids together with a
+/// [snapshot](crate::snapshot()) of the interner.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let s = Symbol::from("keyed");
+/// let key = s.key();
+/// assert_eq!(Symbol::from_key(key), Some(s));
+/// assert_ne!(Symbol::from("other").key(), key);
+/// ```
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SymbolKey(pub(crate) u32);
+
+impl Debug for SymbolKey {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "SymbolKey({})", self.0)
+    }
+}
+
+impl From<Symbol> for SymbolKey {
+    fn from(sym: Symbol) -> SymbolKey {
+        sym.key()
+    }
+}
@@ -0,0 +1,80 @@
+//! Keywords, which are interned separately from symbols.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use crate::{Interner, LocalSymbol, Symbol};
+
+static KEYWORDS: Interner = Interner::new();
+
+/// A keyword, like Lisp's and EDN's `:foo`.
+///
+/// Keywords have their own interner, so a keyword and a symbol with the same name are different
+/// values of different types, and they can't be compared with each other, or with strings, by
+/// mistake. The name doesn't include the colon, but [`Display`] and [`Debug`] show it.
+///
+/// ```
+/// # use symbol::{Keyword, Symbol};
+/// let kw = Keyword::from("foo");
+/// assert_eq!(kw.to_string(), ":foo");
+/// assert_eq!(kw.name(), "foo");
+/// assert_eq!(kw.to_symbol(), Symbol::from("foo"));
+/// assert_eq!(Keyword::from(Symbol::from("foo")), kw);
+/// ```
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Keyword(LocalSymbol<'static>);
+
+impl Keyword {
+    /// Returns the address of the interned name, which is unique among keywords.
+    pub fn addr(self) -> usize {
+        self.0.addr()
+    }
+
+    /// Returns the keyword's id, which is unique among keywords.
+    pub fn id(self) -> u32 {
+        self.0.id()
+    }
+
+    /// Retrieves the name, without the leading colon.
+    pub fn name(self) -> &'static str {
+        self.0.as_str()
+    }
+
+    /// Returns the symbol with the same name.
+    pub fn to_symbol(self) -> Symbol {
+        Symbol::from(self.name())
+    }
+}
+
+impl Debug for Keyword {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Display::fmt(self, fmt)
+    }
+}
+
+impl Display for Keyword {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, ":{}", self.name())
+    }
+}
+
+/// Interns a keyword by name, which shouldn't include the leading colon.
+impl<'a> From<&'a str> for Keyword {
+    fn from(name: &'a str) -> Keyword {
+        Keyword(KEYWORDS.intern(name))
+    }
+}
+
+impl From<Symbol> for Keyword {
+    fn from(sym: Symbol) -> Keyword {
+        Keyword::from(sym.as_str())
+    }
+}
+
+impl From<Keyword> for Symbol {
+    fn from(kw: Keyword) -> Symbol {
+        kw.to_symbol()
+    }
+}
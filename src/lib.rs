@@ -23,11 +23,49 @@
 //! let s5: Symbol = "G#1".into();
 //! assert_eq!(s5, "G#1");
 //!
-//! // symbol notices that G#1 is in use
+//! // gensyms are never equal to interned symbols, even with the same name
 //! let s6 = Symbol::gensym();
-//! assert_eq!(s6, "G#2");
+//! assert_eq!(s6, "G#1");
+//! assert_ne!(s6, s5);
 //! # }
 //! ```
+//!
+//! # Concurrency
+//!
+//! Interning a string that has already been interned never takes a lock, so workloads that intern
+//! the same few identifiers over and over don't contend with each other. Only the first interning
+//! of a string locks, and then only one of the interner's shards.
+//!
+//! # Unicode
+//!
+//! Strings are interned exactly as given, and compared byte for byte, so strings that look the
+//! same but are made of different code points, such as `é` precomposed and `e` followed by a
+//! combining accent, are different symbols. Normalizing needs Unicode's composition tables,
+//! which this crate doesn't carry; a program that wants its symbols normalized can normalize each
+//! string before interning it, for example with the `unicode-normalization` crate, which can check
+//! cheaply whether a string is already normalized:
+//!
+//! ```ignore
+//! use symbol::Symbol;
+//! use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+//!
+//! fn intern_nfc(s: &str) -> Symbol {
+//!     match is_nfc_quick(s.chars()) {
+//!         IsNormalized::Yes => Symbol::from(s),
+//!         _ => Symbol::intern_owned(s.nfc().collect()),
+//!     }
+//! }
+//! ```
+//!
+//! # Multiple Processes
+//!
+//! Each process has its own interner, and a symbol's address means nothing in another process.
+//! Processes that need to agree on symbols can use ids instead, which are the same in every process
+//! that interns the same strings in the same order. With the `mmap` feature, one process can
+//! `save` the strings it has interned, and each worker can `load_mmap` the file before it
+//! interns anything else: the workers then agree on the ids of those symbols, and the operating
+//! system shares one copy of their strings between them. Symbols the workers intern afterwards
+//! are their own.
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
@@ -36,129 +74,2036 @@ extern crate alloc;
 #[macro_use]
 extern crate gc;
 
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
-use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
-use std::mem::{forget, transmute};
-use std::ops::Deref;
-use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::fmt::{Arguments, Debug, Display, Formatter, Result as FmtResult};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Deref};
+#[cfg(feature = "debug-intern-sites")]
+use std::panic::Location;
+use std::str::{FromStr, Utf8Error};
+use std::sync::Arc;
 
 #[cfg(not(feature = "std"))]
-use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
 
 #[cfg(not(feature = "std"))]
 mod std {
-    pub mod collections {
-        pub use alloc::collections::BTreeSet;
+    pub mod borrow {
+        pub use alloc::borrow::Cow;
     }
     pub mod cmp {
         pub use core::cmp::Ordering;
     }
     pub mod fmt {
-        pub use core::fmt::{Debug, Display, Formatter, Result};
+        pub use core::fmt::{Arguments, Debug, Display, Formatter, Result, Write};
+    }
+    pub mod hash {
+        pub use core::hash::{BuildHasher, Hash, Hasher};
     }
     pub mod mem {
-        pub use core::mem::{forget, transmute};
+        pub use core::mem::{align_of, size_of, size_of_val, take, ManuallyDrop, MaybeUninit};
+    }
+    pub mod num {
+        pub use core::num::NonZeroU32;
     }
     pub mod ops {
-        pub use core::ops::Deref;
+        pub use core::ops::{Add, Deref, Index, IndexMut, Range};
+    }
+    #[cfg(feature = "debug-intern-sites")]
+    pub mod panic {
+        pub use core::panic::Location;
+    }
+    pub mod ptr {
+        pub use core::ptr::{
+            copy_nonoverlapping, eq, null_mut, slice_from_raw_parts, slice_from_raw_parts_mut,
+            NonNull,
+        };
+    }
+    pub mod slice {
+        pub use core::slice::{from_raw_parts, from_raw_parts_mut, from_ref};
+    }
+    pub mod str {
+        pub use core::str::{from_utf8, from_utf8_unchecked, FromStr, Utf8Error};
     }
     pub mod sync {
-        pub mod atomic {
-            pub use core::sync::atomic::{AtomicUsize, Ordering};
-        }
+        pub use alloc::sync::Arc;
     }
 }
 
-use once_cell::sync::Lazy;
-use spin::Mutex;
+mod arena;
+mod atomic;
+mod bitset;
+#[cfg(feature = "thread-local-cache")]
+mod cache;
+mod ci;
+mod format;
+#[cfg(feature = "std")]
+mod gensym;
+mod ident;
+mod interned;
+mod interner;
+#[cfg(feature = "isolation")]
+mod isolation;
+mod key;
+mod keyword;
+mod local;
+mod map;
+mod matcher;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "mmap")]
+mod persist;
+#[cfg(feature = "props")]
+mod props;
+mod qualified;
+mod rc;
+#[cfg(feature = "shared-heap")]
+mod shared;
+mod snapshot;
+mod stable;
+mod statics;
+mod symbol32;
+mod symbol_vec;
+mod sync;
+mod table;
+
+use crate::format::Formatted;
+use crate::interner::{Checkpoint, Entry, SymbolData, VacantEntry};
+
+pub use crate::atomic::{AtomicOptionSymbol, AtomicSymbol};
+pub use crate::bitset::SymbolBitSet;
+pub use crate::ci::CiSymbol;
+pub use crate::ident::{Ident, Mark, SyntaxContext};
+pub use crate::interned::{InternIter, Interned};
+pub use crate::interner::{Interner, Limits, Stats};
+#[cfg(feature = "isolation")]
+pub use crate::isolation::{isolate, IsolationGuard};
+pub use crate::key::SymbolKey;
+pub use crate::keyword::Keyword;
+pub use crate::local::LocalSymbol;
+pub use crate::map::{BuildSymbolHasher, SymbolHasher, SymbolMap, SymbolMapEntry, SymbolSet};
+pub use crate::matcher::{FindIter, SymbolMatch, SymbolMatcher};
+#[cfg(feature = "metrics")]
+pub use crate::metrics::InternerMetrics;
+#[cfg(feature = "mmap")]
+pub use crate::persist::{load_mmap, save};
+pub use crate::qualified::QualifiedSymbol;
+pub use crate::rc::RcSymbol;
+pub use crate::snapshot::InternerSnapshot;
+pub use crate::stable::StableSymbol;
+pub use crate::statics::StaticSymbol;
+pub use crate::symbol32::Symbol32;
+pub use crate::symbol_vec::SymbolVec;
+pub use crate::table::SymbolTable;
+#[cfg(feature = "derive")]
+pub use symbol_derive::SymbolEnum;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::statics::StaticSymbols;
+}
+
+#[cfg(not(feature = "shared-heap"))]
+static SYMBOL_HEAP: Interner = Interner::new();
 
-static SYMBOL_HEAP: Lazy<Mutex<BTreeSet<&'static str>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+/// Returns the interner symbols come from on this thread.
+fn heap() -> &'static Interner {
+    #[cfg(feature = "isolation")]
+    if let Some(interner) = isolation::current() {
+        return interner;
+    }
+    #[cfg(feature = "shared-heap")]
+    return shared::global();
+    #[cfg(not(feature = "shared-heap"))]
+    &SYMBOL_HEAP
+}
 
 /// An interned string with O(1) equality.
-#[allow(clippy::derived_hash_with_manual_eq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
-#[cfg_attr(feature = "serde", serde(transparent))]
-#[derive(Clone, Copy, Eq, Hash)]
+///
+/// A `Symbol` is a single pointer to the interner's record of its string. Short strings are not
+/// stored inline instead: that would stop [`as_str`](Symbol::as_str) from returning a
+/// `&'static str`, and would add a branch to every comparison. Interning a string that is already
+/// present doesn't lock, so even for short strings the cost is just a hash and a probe.
+///
+/// The pointer is not to the string itself, since the record also holds the string's hash and the
+/// symbol's id, but `Symbol` is guaranteed to be laid out as a non-null pointer, and
+/// `Option<Symbol>` as a nullable one. [`into_raw`](Symbol::into_raw) and
+/// [`from_raw`](Symbol::from_raw) convert to and from the pointer.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
 pub struct Symbol {
-    s: &'static str,
+    data: &'static SymbolData,
 }
 
 impl Symbol {
     /// Retrieves the address of the backing string.
+    ///
+    /// Symbols with different addresses are different, but addresses change from run to run, so
+    /// they mustn't be saved anywhere that outlives the process. To refer to a symbol from a side
+    /// table, use its [`key`](Symbol::key) instead.
     pub fn addr(self) -> usize {
-        self.s.as_ptr() as usize
+        self.as_str().as_ptr() as usize
     }
 
     /// Retrieves the string from the Symbol.
     pub fn as_str(self) -> &'static str {
-        self.s
+        self.data.as_str()
+    }
+
+    /// Retrieves the symbol's id.
+    ///
+    /// Ids are handed out densely, starting from zero, in the order symbols are created, so they
+    /// can be used to index into a `Vec` of per-symbol data.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let s = Symbol::from("asdf");
+    /// assert_eq!(Symbol::from_id(s.id()), Some(s));
+    /// ```
+    pub fn id(self) -> u32 {
+        self.data.id()
+    }
+
+    /// Retrieves the 64-bit hash of the symbol's string, which was computed when it was interned.
+    ///
+    /// This is exactly what the symbol's `Hash` impl feeds to a hasher, so a hasher that passes
+    /// integers through unchanged (such as `nohash-hasher`'s, with the `nohash-hasher` feature)
+    /// can key a map by symbols without doing any hashing of its own. Distinct symbols usually have
+    /// distinct hashes, but this is not guaranteed.
+    pub fn identity_hash(self) -> u64 {
+        self.data.hash()
+    }
+
+    /// Returns where in the source the symbol's string was first interned, or where the symbol was
+    /// made, for a gensym or an uninterned symbol. Requires the `debug-intern-sites` feature.
+    ///
+    /// The location is that of the first call into this crate, such as the `Symbol::from` or the
+    /// `.into()`, rather than somewhere inside it.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// use std::panic::Location;
+    ///
+    /// let (s, here) = (Symbol::from("first_interned_at"), Location::caller());
+    /// assert_eq!(s.first_interned_at().line(), here.line());
+    ///
+    /// // Interning the string again doesn't move it.
+    /// let t = Symbol::from("first_interned_at");
+    /// assert_eq!(t.first_interned_at().line(), here.line());
+    /// ```
+    #[cfg(feature = "debug-intern-sites")]
+    pub fn first_interned_at(self) -> &'static Location<'static> {
+        self.data.site()
+    }
+
+    /// Compares the symbols' ids, as `Ord` does.
+    ///
+    /// This is the order symbols were created in. It's cheap, and the same wherever the same
+    /// strings are interned in the same order, but otherwise has no meaning.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let b = Symbol::from("cmp_by_id b");
+    /// let a = Symbol::from("cmp_by_id a");
+    /// assert_eq!(b.cmp_by_id(&a), b.cmp(&a));
+    /// assert!(b < a);
+    /// ```
+    pub fn cmp_by_id(&self, other: &Symbol) -> Ordering {
+        // Symbols from different interners can have the same id, and even the same string, so
+        // they're told apart by their entries, which is what `==` compares.
+        self.id()
+            .cmp(&other.id())
+            .then_with(|| (self.data as *const SymbolData).cmp(&(other.data as *const SymbolData)))
+    }
+
+    /// Compares the symbols' strings, rather than their ids as `Ord` does.
+    ///
+    /// This is the order to use for output people read, such as sorted lists of names. To sort a
+    /// slice, use `sort_by(Symbol::cmp_by_str)`; to keep symbols in string order in a `BTreeMap` or
+    /// `BTreeSet`, key it by [`as_str`](Symbol::as_str), which is `'static`.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let mut syms = [Symbol::from("b"), Symbol::from("c"), Symbol::from("a")];
+    /// syms.sort_by(Symbol::cmp_by_str);
+    /// assert_eq!(syms, ["a", "b", "c"]);
+    /// ```
+    pub fn cmp_by_str(&self, other: &Symbol) -> Ordering {
+        if self == other {
+            return Ordering::Equal;
+        }
+        self.as_str().cmp(other.as_str())
+    }
+
+    /// Returns the symbol for the empty string, which is also what `Symbol::default()` returns.
+    ///
+    /// This isn't a constant, since a symbol points into the interner, and a constant can't point
+    /// into a `static` on the versions of Rust this crate supports. Once the empty string has been
+    /// interned, though, this is just a lookup.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// #[derive(Default)]
+    /// struct Field {
+    ///     name: Symbol,
+    /// }
+    ///
+    /// assert_eq!(Field::default().name, Symbol::empty());
+    /// assert_eq!(Symbol::empty(), "");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn empty() -> Symbol {
+        Symbol::from("")
+    }
+
+    /// Returns the symbol's key, an opaque identifier to store in side tables.
+    ///
+    /// See [`SymbolKey`].
+    pub fn key(self) -> SymbolKey {
+        SymbolKey(self.id())
+    }
+
+    /// Retrieves the symbol with the given key, if it still exists.
+    pub fn from_key(key: SymbolKey) -> Option<Symbol> {
+        Symbol::from_id(key.0)
+    }
+
+    /// Returns the pointer the symbol is made of, for storing it where only a raw pointer fits,
+    /// such as the `void *` user data of a C library's callbacks.
+    ///
+    /// The pointer isn't to the symbol's string, but to the interner's record of it, which is what
+    /// a symbol holds. A `&'static str` can't stand in for it: the string of a symbol made with
+    /// [`from_static`](Symbol::from_static) is stored apart from its record, and an uninterned
+    /// symbol can have the same string as an interned one, so the symbol couldn't be found from
+    /// the string without a lookup that would sometimes find the wrong one. The pointer's only use is to turn
+    /// it back into the symbol with [`from_raw`](Symbol::from_raw); for the string, use
+    /// [`as_str`](Symbol::as_str), which returns a `&'static str`.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let s = Symbol::from("raw");
+    /// let ptr = s.into_raw();
+    /// // SAFETY: The pointer came from `into_raw`, and nothing has been cleared since.
+    /// assert_eq!(unsafe { Symbol::from_raw(ptr) }, s);
+    /// ```
+    pub fn into_raw(self) -> *const () {
+        self.data as *const SymbolData as *const ()
+    }
+
+    /// Turns a pointer from [`into_raw`](Symbol::into_raw) back into its symbol.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have come from `into_raw`, and the symbol must still be valid: it mustn't
+    /// have been removed by [`unsafe_clear`], [`collect`], or dropping a [`checkpoint`] guard.
+    pub unsafe fn from_raw(ptr: *const ()) -> Symbol {
+        Symbol {
+            data: &*(ptr as *const SymbolData),
+        }
+    }
+
+    /// Retrieves the symbol with the given id, if there is one.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let s = Symbol::from("by id");
+    /// assert_eq!(Symbol::from_id(s.id()), Some(s));
+    /// assert!(Symbol::from_id(u32::MAX).is_none());
+    /// ```
+    pub fn from_id(id: u32) -> Option<Symbol> {
+        heap().get_by_id(id).map(|data| Symbol { data })
+    }
+
+    /// Interns every string from an iterator.
+    ///
+    /// This is faster than converting each string on its own, since each of the interner's locks is
+    /// taken at most once for the whole batch.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let syms = Symbol::intern_all(["fn", "main", "fn"]);
+    /// assert_eq!(syms, ["fn", "main", "fn"]);
+    /// assert_eq!(syms[0].addr(), syms[2].addr());
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn intern_all<I>(iter: I) -> Vec<Symbol>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let items = iter.into_iter().collect::<Vec<_>>();
+        let strs = items.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        heap()
+            .intern_all(&strs)
+            .into_iter()
+            .map(|data| Symbol { data })
+            .collect()
+    }
+
+    /// Returns the symbol for a string if it has already been interned, without interning it
+    /// otherwise.
+    ///
+    /// Unlike interning, this never allocates or locks, so it is safe to use on untrusted input
+    /// without every distinct string it is given staying in memory forever.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let s = Symbol::from("known");
+    /// assert_eq!(Symbol::try_get("known"), Some(s));
+    /// assert_eq!(Symbol::try_get("unknown"), None);
+    /// assert!(!symbol::is_interned("unknown"));
+    /// ```
+    pub fn try_get(s: &str) -> Option<Symbol> {
+        #[cfg(feature = "thread-local-cache")]
+        if let Some(data) = cache::get(s) {
+            return Some(Symbol { data });
+        }
+
+        let hash = heap().hash(s);
+        heap().get(hash, s).map(|data| Symbol { data })
+    }
+
+    /// Interns a string that the caller owns.
+    ///
+    /// If the string is new and long enough to need an allocation of its own, the interner keeps
+    /// its buffer rather than copying it. Shorter strings are copied next to the interner's other
+    /// strings, which costs no allocation either, and the buffer is freed. `From<String>` does the
+    /// same.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let long = "x".repeat(4096);
+    /// let ptr = long.as_ptr();
+    /// let sym = Symbol::intern_owned(long);
+    /// assert_eq!(sym.as_str().as_ptr(), ptr);
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn intern_owned(s: String) -> Symbol {
+        #[cfg(feature = "thread-local-cache")]
+        if let Some(data) = cache::get(&s) {
+            return Symbol { data };
+        }
+
+        let hash = heap().hash(&s);
+        let data = match heap().entry(hash, &s) {
+            Entry::Occupied(data) => data,
+            Entry::Vacant(entry) => entry.insert_owned(s),
+        };
+        #[cfg(feature = "thread-local-cache")]
+        cache::put(data);
+        Symbol { data }
+    }
+
+    /// Interns a string given as bytes, if they are valid UTF-8. This is also what
+    /// `Symbol::try_from(bytes)` does.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// assert_eq!(Symbol::from_utf8(b"bytes"), Ok(Symbol::from("bytes")));
+    /// assert!(Symbol::from_utf8(b"\xff").is_err());
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn from_utf8(bytes: &[u8]) -> Result<Symbol, Utf8Error> {
+        let s = std::str::from_utf8(bytes)?;
+        Ok(Symbol::from(s))
+    }
+
+    /// Interns a string given as bytes, without checking that they are valid UTF-8.
+    ///
+    /// This is for lexers that have already checked their whole input, and slice tokens out of it
+    /// at character boundaries.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be valid UTF-8, as for [`str::from_utf8_unchecked`]. Interning bytes that
+    /// aren't is undefined behavior, since the interner and every user of the symbol treat them as
+    /// a `str`.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let input = "let x = 1;";
+    /// let token = &input.as_bytes()[4..5];
+    /// // SAFETY: The token was sliced out of a `str` at character boundaries.
+    /// let sym = unsafe { Symbol::from_utf8_unchecked(token) };
+    /// assert_eq!(sym, "x");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub unsafe fn from_utf8_unchecked(bytes: &[u8]) -> Symbol {
+        Symbol::from(std::str::from_utf8_unchecked(bytes))
+    }
+
+    /// Interns a string if it is an identifier, by the rules of [Unicode Standard Annex #31]: it
+    /// must start with a character that has the `XID_Start` property, or an underscore, and the
+    /// rest of its characters must have `XID_Continue`. These are the rules Rust uses. Requires the
+    /// `unicode-ident` feature.
+    ///
+    /// [Unicode Standard Annex #31]: https://www.unicode.org/reports/tr31/
+    ///
+    /// ```
+    /// # use symbol::{IdentError, Symbol};
+    /// assert_eq!(Symbol::new_ident("größe"), Ok(Symbol::from("größe")));
+    /// assert_eq!(Symbol::new_ident("_private"), Ok(Symbol::from("_private")));
+    /// assert_eq!(Symbol::new_ident(""), Err(IdentError::Empty));
+    /// assert_eq!(
+    ///     Symbol::new_ident("a-b"),
+    ///     Err(IdentError::InvalidChar { offset: 1, ch: '-' }),
+    /// );
+    /// assert!(!symbol::is_interned("a-b"));
+    /// ```
+    #[cfg(feature = "unicode-ident")]
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn new_ident(s: &str) -> Result<Symbol, IdentError> {
+        let mut chars = s.char_indices();
+        match chars.next() {
+            None => return Err(IdentError::Empty),
+            Some((_, ch)) if ch != '_' && !unicode_ident::is_xid_start(ch) => {
+                return Err(IdentError::InvalidChar { offset: 0, ch })
+            }
+            Some(_) => {}
+        }
+        if let Some((offset, ch)) = chars.find(|&(_, ch)| !unicode_ident::is_xid_continue(ch)) {
+            return Err(IdentError::InvalidChar { offset, ch });
+        }
+        Ok(Symbol::from(s))
+    }
+
+    /// Interns a string that lives forever, such as a literal, without copying it if it is new.
+    ///
+    /// This is an ordinary interning: the result is the same symbol `Symbol::from` gives for the
+    /// string, whichever of the two interns it first, so the two can be mixed freely. Only where
+    /// the string is stored differs.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// static NAME: &str = "from-static";
+    /// let sym = Symbol::from_static(NAME);
+    /// assert_eq!(sym.as_str().as_ptr(), NAME.as_ptr());
+    /// assert_eq!(sym, Symbol::from("from-static"));
+    /// assert_eq!(Symbol::from_static("from-static"), sym);
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn from_static(s: &'static str) -> Symbol {
+        #[cfg(feature = "thread-local-cache")]
+        if let Some(data) = cache::get(s) {
+            return Symbol { data };
+        }
+
+        let hash = heap().hash(s);
+        let data = match heap().entry(hash, s) {
+            Entry::Occupied(data) => data,
+            Entry::Vacant(entry) => entry.insert_static(s),
+        };
+        #[cfg(feature = "thread-local-cache")]
+        cache::put(data);
+        Symbol { data }
+    }
+
+    /// Interns the string that `format_args!` output formats to.
+    ///
+    /// Short strings are formatted into a buffer on the stack, so unlike interning a `format!`ed
+    /// `String`, this doesn't allocate when the symbol already exists. Long ones are formatted into
+    /// a `String`, which is kept as with [`intern_owned`](Symbol::intern_owned) if the symbol is new.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let (base, i) = ("tmp", 3);
+    /// let sym = Symbol::from_fmt(format_args!("{}_{}", base, i));
+    /// assert_eq!(sym, "tmp_3");
+    /// assert_eq!(sym, Symbol::from("tmp_3"));
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn from_fmt(args: Arguments) -> Symbol {
+        if let Some(s) = args.as_str() {
+            return Symbol::from(s);
+        }
+        Symbol::from_formatted(Formatted::new(args))
+    }
+
+    /// Interns the concatenation of some strings, which are copied into one buffer, as with
+    /// [`from_fmt`](Symbol::from_fmt).
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let ty = Symbol::from("Vec");
+    /// assert_eq!(Symbol::concat([ty.as_str(), "_", "push"]), "Vec_push");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn concat<I>(parts: I) -> Symbol
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Symbol::join(parts, "")
+    }
+
+    /// Interns the concatenation of some strings, with a separator between each pair, which are
+    /// copied into one buffer, as with [`from_fmt`](Symbol::from_fmt).
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let path = [Symbol::from("std"), Symbol::from("mem"), Symbol::from("swap")];
+    /// assert_eq!(Symbol::join(path, "::"), "std::mem::swap");
+    /// assert_eq!(Symbol::join(Vec::<&str>::new(), "::"), "");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn join<I>(parts: I, sep: &str) -> Symbol
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut out = Formatted::empty();
+        for (i, part) in parts.into_iter().enumerate() {
+            if i != 0 {
+                out.push_str(sep);
+            }
+            out.push_str(part.as_ref());
+        }
+        Symbol::from_formatted(out)
+    }
+
+    /// Interns formatted output, keeping its `String` if it needed one and the symbol is new.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from_formatted(formatted: Formatted) -> Symbol {
+        match formatted {
+            Formatted::Heap(s) => Symbol::intern_owned(s),
+            inline => Symbol::from(inline.as_str()),
+        }
+    }
+
+    /// Interns a string, failing instead of creating a new symbol if the interner doesn't allow
+    /// it, because it is [frozen](freeze) or the string is over its [limits](set_limits).
+    ///
+    /// ```
+    /// # use symbol::{InternError, Symbol};
+    /// let s = Symbol::try_intern("loaded").unwrap();
+    /// symbol::freeze();
+    /// assert_eq!(Symbol::try_intern("loaded"), Ok(s));
+    /// assert_eq!(Symbol::try_intern("unseen"), Err(InternError::Frozen));
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn try_intern(s: &str) -> Result<Symbol, InternError> {
+        heap().try_intern_data(s).map(|data| Symbol { data })
+    }
+
+    /// Interns a string without waiting for a lock, for code that mustn't block, such as an async
+    /// task or an audio callback.
+    ///
+    /// If the string is already interned, this finds it without locking, as usual. Otherwise, it
+    /// needs the lock of one of the interner's shards, and returns `None` if another thread holds
+    /// it, so that the caller can fall back to something else or try again later. Inserting a new
+    /// string may still allocate.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let s = Symbol::try_intern_nonblocking("event").unwrap_or_else(|| Symbol::from("event"));
+    /// assert_eq!(s, "event");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn try_intern_nonblocking(s: &str) -> Option<Symbol> {
+        #[cfg(feature = "thread-local-cache")]
+        if let Some(data) = cache::get(s) {
+            return Some(Symbol { data });
+        }
+
+        let hash = heap().hash(s);
+        let data = match heap().try_entry(hash, s)? {
+            Entry::Occupied(data) => data,
+            Entry::Vacant(entry) => entry.insert(s),
+        };
+        #[cfg(feature = "thread-local-cache")]
+        cache::put(data);
+        Some(Symbol { data })
+    }
+
+    /// Makes a new symbol with the given name that isn't interned, like Lisp's `make-symbol`.
+    ///
+    /// The symbol is only equal to itself: interning the same string, or making another
+    /// uninterned symbol with it, gives a different symbol, and looking the string up never finds
+    /// it. This suits temporaries introduced by a macro expander, which mustn't collide with the
+    /// user's identifiers. Otherwise, it works like any other symbol, and has an id of its own. It
+    /// is left out of [`snapshot`]s.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let x = Symbol::from("x");
+    /// let tmp = Symbol::uninterned("x");
+    /// assert_eq!(tmp, "x");
+    /// assert_ne!(tmp, x);
+    /// assert_ne!(tmp, Symbol::uninterned("x"));
+    /// assert_eq!(Symbol::try_get("x"), Some(x));
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn uninterned(name: &str) -> Symbol {
+        Symbol {
+            data: heap().uninterned(name),
+        }
+    }
+
+    /// Generates a new symbol with a name of the form `prefix#n`, like [`gensym`](Symbol::gensym).
+    ///
+    /// Each prefix has a counter of its own, so the names generated with one count up from zero
+    /// and are easy to follow in generated code.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// assert_eq!(Symbol::gensym_with("tmp"), "tmp#0");
+    /// assert_eq!(Symbol::gensym_with("label"), "label#0");
+    /// assert_eq!(Symbol::gensym_with("tmp"), "tmp#1");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn gensym_with(prefix: &str) -> Symbol {
+        let n = heap().next_with_stem(Formatted::new(format_args!("{}#", prefix)).as_str());
+        let name = Formatted::new(format_args!("{}#{}", prefix, n));
+        Symbol {
+            data: heap().gensym(name.as_str()),
+        }
+    }
+
+    /// Returns whether the symbol was made by [`gensym`](Symbol::gensym) or
+    /// [`gensym_with`](Symbol::gensym_with).
+    ///
+    /// This is recorded when the symbol is made, so it doesn't depend on what its name looks like.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// assert!(Symbol::gensym().is_gensym());
+    /// assert!(!Symbol::from("G#1000").is_gensym());
+    /// ```
+    pub fn is_gensym(self) -> bool {
+        self.data.is_gensym()
+    }
+
+    /// Makes a new name based on an existing one, for renaming a variable so that it doesn't
+    /// shadow or capture another.
+    ///
+    /// This tries `base1`, `base2`, and so on, and interns and returns the first that isn't
+    /// interned already. Any digits at the end of `base` are replaced, so freshening `x1` gives
+    /// `x2` rather than `x11`. Each base remembers where it got to, so freshening the same one
+    /// repeatedly doesn't try the same names over again.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let x = Symbol::from("x");
+    /// let _ = Symbol::from("x2");
+    /// let x1 = Symbol::fresh(x);
+    /// assert_eq!(x1, "x1");
+    /// assert_eq!(Symbol::fresh(x), "x3");
+    /// assert_eq!(Symbol::fresh(x1), "x4");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn fresh(base: Symbol) -> Symbol {
+        let stem = match base.as_str().trim_end_matches(|c: char| c.is_ascii_digit()) {
+            "" => base.as_str(),
+            stem => stem,
+        };
+        let data = loop {
+            let n = format!("{}{}", stem, heap().next_with_stem(stem) + 1);
+            let hash = heap().hash(&n);
+            if let Entry::Vacant(entry) = heap().entry(hash, &n) {
+                break entry.insert(&n);
+            }
+        };
+        Symbol { data }
     }
 
-    /// Generates a new symbol with a name of the form `G#n`, where `n` is some positive integer.
+    /// Generates a new symbol with a name of the form `G#n`, where `n` counts up from zero.
+    ///
+    /// With the `std` feature, each thread takes numbers from the counter in blocks, so threads
+    /// making gensyms at the same time don't contend for it. A thread's gensyms still count up,
+    /// but numbers can be skipped, and another thread's gensyms may come in between.
+    ///
+    /// Gensyms aren't interned, like symbols from [`uninterned`](Symbol::uninterned): interning
+    /// `G#n` gives a different symbol, so a gensym can never be confused with a name from the
+    /// program's input. Making one is just a counter increment and storing its name.
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
     pub fn gensym() -> Symbol {
-        static N: Lazy<AtomicUsize> = Lazy::new(|| AtomicUsize::new(0));
+        let name = Formatted::new(format_args!("G#{}", heap().next_gensym()));
+        Symbol {
+            data: heap().gensym(name.as_str()),
+        }
+    }
+}
+
+/// Interns a formatted string, taking the same arguments as `format!`.
+///
+/// This is [`Symbol::from_fmt`] with `format_args!`: short strings are formatted on the stack, so
+/// nothing is allocated when the symbol already exists.
+///
+/// ```
+/// # use symbol::{sym_format, Symbol};
+/// let (ty, method) = (Symbol::from("Vec"), "push");
+/// assert_eq!(sym_format!("{}_{}", ty, method), "Vec_push");
+/// ```
+#[macro_export]
+macro_rules! sym_format {
+    ($($arg:tt)*) => {
+        $crate::Symbol::from_fmt(::core::format_args!($($arg)*))
+    };
+}
+
+/// Transformations, which make new symbols from the strings of existing ones.
+///
+/// Each returns the symbol it was called on when the transformation wouldn't change its string, so
+/// normalizing a symbol that is already normal costs a scan of its string, and no interning.
+impl Symbol {
+    /// Interns the lowercase version of the symbol's string, as with [`str::to_lowercase`].
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let select = Symbol::from("SELECT");
+    /// assert_eq!(select.to_lowercase_sym(), "select");
+    /// assert_eq!(Symbol::from("ΣΑΣ").to_lowercase_sym(), "σας");
+    /// let lower = Symbol::from("from");
+    /// assert_eq!(lower.to_lowercase_sym().addr(), lower.addr());
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn to_lowercase_sym(self) -> Symbol {
+        let s = self.as_str();
+        // A string of characters that are each their own lowercase is unchanged, even with the
+        // special case for final sigmas, since capital sigma isn't.
+        if s.chars().all(|c| c.to_lowercase().eq([c])) {
+            return self;
+        }
+        Symbol::intern_owned(s.to_lowercase())
+    }
+
+    /// Interns the uppercase version of the symbol's string, as with [`str::to_uppercase`].
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// assert_eq!(Symbol::from("straße").to_uppercase_sym(), "STRASSE");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn to_uppercase_sym(self) -> Symbol {
+        let s = self.as_str();
+        if s.chars().all(|c| c.to_uppercase().eq([c])) {
+            return self;
+        }
+        Symbol::intern_owned(s.to_uppercase())
+    }
+
+    /// Interns the symbol's string with its ASCII letters made lowercase, as with
+    /// [`str::to_ascii_lowercase`]. This doesn't allocate unless the result is new and long.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// assert_eq!(Symbol::from("ÉCOLE").to_ascii_lowercase_sym(), "École");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn to_ascii_lowercase_sym(self) -> Symbol {
+        if !self.bytes().any(|b| b.is_ascii_uppercase()) {
+            return self;
+        }
+        Symbol::from_chars(self.chars().map(|c| c.to_ascii_lowercase()))
+    }
+
+    /// Interns the symbol's string with its ASCII letters made uppercase, as with
+    /// [`str::to_ascii_uppercase`]. This doesn't allocate unless the result is new and long.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// assert_eq!(Symbol::from("école").to_ascii_uppercase_sym(), "éCOLE");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn to_ascii_uppercase_sym(self) -> Symbol {
+        if !self.bytes().any(|b| b.is_ascii_lowercase()) {
+            return self;
+        }
+        Symbol::from_chars(self.chars().map(|c| c.to_ascii_uppercase()))
+    }
+
+    /// Interns what is left of the symbol's string after a prefix, if it starts with the prefix.
+    ///
+    /// The rest of the string is copied, even though it is part of a string the interner already
+    /// has, since the symbol it came from may be collected.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let var = Symbol::from("$home");
+    /// assert_eq!(var.strip_prefix_sym("$"), Some(Symbol::from("home")));
+    /// assert_eq!(var.strip_prefix_sym("@"), None);
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn strip_prefix_sym(self, prefix: &str) -> Option<Symbol> {
+        if prefix.is_empty() {
+            return Some(self);
+        }
+        let rest = self.as_str().strip_prefix(prefix)?;
+        Some(Symbol::from(rest))
+    }
+
+    /// Interns what is left of the symbol's string before a suffix, if it ends with the suffix.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let ctor = Symbol::from("Vec_new");
+    /// assert_eq!(ctor.strip_suffix_sym("_new"), Some(Symbol::from("Vec")));
+    /// assert_eq!(ctor.strip_suffix_sym("_drop"), None);
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn strip_suffix_sym(self, suffix: &str) -> Option<Symbol> {
+        if suffix.is_empty() {
+            return Some(self);
+        }
+        let rest = self.as_str().strip_suffix(suffix)?;
+        Some(Symbol::from(rest))
+    }
+
+    /// Interns the string a function makes from the symbol's string.
+    ///
+    /// The function's `String` is kept as the new symbol's storage if it needs one, as with
+    /// [`intern_owned`](Symbol::intern_owned).
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let name = Symbol::from("my-crate");
+    /// assert_eq!(name.map(|s| s.replace('-', "_")), "my_crate");
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn map(self, f: impl FnOnce(&str) -> String) -> Symbol {
+        let s = f(self.as_str());
+        if s == self.as_str() {
+            return self;
+        }
+        Symbol::intern_owned(s)
+    }
+
+    /// Like [`map`](Symbol::map), for a function that can fail.
+    ///
+    /// ```
+    /// # use symbol::Symbol;
+    /// let unescape = |s: &str| match s.strip_prefix('\\') {
+    ///     Some(rest) => Ok(rest.to_owned()),
+    ///     None => Err("not escaped"),
+    /// };
+    /// assert_eq!(Symbol::from("\\n").try_map(unescape), Ok(Symbol::from("n")));
+    /// assert_eq!(Symbol::from("n").try_map(unescape), Err("not escaped"));
+    /// ```
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    pub fn try_map<E>(self, f: impl FnOnce(&str) -> Result<String, E>) -> Result<Symbol, E> {
+        let s = f(self.as_str())?;
+        if s == self.as_str() {
+            return Ok(self);
+        }
+        Ok(Symbol::intern_owned(s))
+    }
+
+    /// Interns a string made of some characters, copied into one buffer as with
+    /// [`from_fmt`](Symbol::from_fmt).
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from_chars(chars: impl Iterator<Item = char>) -> Symbol {
+        let mut out = Formatted::empty();
+        for c in chars {
+            out.push_str(c.encode_utf8(&mut [0; 4]));
+        }
+        Symbol::from_formatted(out)
+    }
+}
+
+/// Properties, which attach values to a symbol under other symbols as keys, like the property
+/// lists of Lisp symbols. Requires the `props` feature.
+///
+/// Each property can hold a value of any type. A property is removed along with its symbol or its
+/// key, by [`collect`], [`unsafe_clear`], or dropping a [`checkpoint`] guard.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let car = Symbol::from("car");
+/// let arity = Symbol::from("arity");
+/// car.set_prop(arity, 1usize);
+/// assert_eq!(car.prop::<usize>(arity), Some(1));
+/// assert_eq!(car.prop::<u32>(arity), None);
+/// assert!(car.remove_prop(arity));
+/// assert_eq!(car.prop::<usize>(arity), None);
+/// ```
+#[cfg(feature = "props")]
+impl Symbol {
+    /// Returns a copy of the value of a property, or `None` if it isn't set or holds a value of
+    /// another type.
+    pub fn prop<T: core::any::Any + Clone>(self, key: Symbol) -> Option<T> {
+        heap().props.get(self.id(), key.id())
+    }
+
+    /// Sets a property, replacing any value it had, whatever its type.
+    pub fn set_prop<T: core::any::Any + Send + Sync>(self, key: Symbol, value: T) {
+        heap().props.set(self.id(), key.id(), value)
+    }
+
+    /// Removes a property, returning whether it was set.
+    pub fn remove_prop(self, key: Symbol) -> bool {
+        heap().props.remove(self.id(), key.id())
+    }
+}
+
+/// Starts [`Symbol::gensym`] counting from zero again, and likewise [`Symbol::gensym_with`] and
+/// [`Symbol::fresh`] for each of their prefixes.
+///
+/// This makes generated names predictable, for tests that compare output containing them against
+/// saved copies. Gensyms made before the reset keep their names, so a new gensym can have the same
+/// name as an old one, though the two are still different symbols. Tests running in parallel share
+/// the counters, so they should also use `isolate` (with the `isolation` feature) to keep from
+/// affecting each other's names.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let old = Symbol::gensym_with("tmp");
+/// symbol::gensym_reset();
+/// assert_eq!(Symbol::gensym(), "G#0");
+/// let new = Symbol::gensym_with("tmp");
+/// assert_eq!(new, "tmp#0");
+/// assert_ne!(new, old);
+/// ```
+pub fn gensym_reset() {
+    heap().set_gensym(0);
+    heap().reset_stems();
+}
+
+/// Makes the next [`Symbol::gensym`] be named `G#n`, going on from there.
+///
+/// ```
+/// # use symbol::Symbol;
+/// symbol::gensym_set(100);
+/// assert_eq!(Symbol::gensym(), "G#100");
+/// assert_eq!(Symbol::gensym(), "G#101");
+/// ```
+pub fn gensym_set(n: usize) {
+    heap().set_gensym(n);
+}
+
+/// Interns the given strings ahead of time.
+///
+/// Calling this at startup with the identifiers a program knows it will need means that later
+/// threads find them already interned, rather than contending to insert them.
+#[cfg_attr(feature = "debug-intern-sites", track_caller)]
+pub fn preintern(strs: &[&str]) {
+    heap().intern_all(strs);
+}
+
+/// Interns strings that live forever ahead of time, as with [`preintern`], but without copying
+/// them.
+///
+/// The interner keeps references to the new strings, as with [`Symbol::from_static`], so a large
+/// table built into the program, such as generated schema field names, costs the interner no
+/// extra memory for their contents.
+///
+/// ```
+/// # use symbol::Symbol;
+/// static FIELDS: &[&str] = &["registered_id", "registered_name"];
+/// symbol::register_static(FIELDS);
+/// assert_eq!(Symbol::from("registered_id").as_str().as_ptr(), FIELDS[0].as_ptr());
+/// ```
+#[cfg_attr(feature = "debug-intern-sites", track_caller)]
+pub fn register_static(strs: &'static [&'static str]) {
+    heap().intern_all_static(strs);
+}
+
+/// Interns every non-empty line of a word list ahead of time, as with [`preintern`].
+///
+/// This is meant for lists built into the program, such as a compiler's keywords and builtins, so
+/// that they are interned before anything else runs; [`preintern_file!`] does this for a file
+/// embedded with `include_str!`. Leading and trailing whitespace is removed from each line.
+///
+/// ```
+/// symbol::preintern_list("fn\nlet\n\nmatch\n");
+/// assert!(symbol::is_interned("let"));
+/// assert!(!symbol::is_interned(""));
+/// ```
+#[cfg_attr(feature = "debug-intern-sites", track_caller)]
+pub fn preintern_list(list: &str) {
+    let strs = list
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    heap().intern_all(&strs);
+}
+
+/// Embeds a newline-separated word list in the program with `include_str!`, and interns its words
+/// with [`preintern_list`].
+///
+/// The path is relative to the file the macro is used in, as with `include_str!`.
+///
+/// ```ignore
+/// fn main() {
+///     symbol::preintern_file!("keywords.txt");
+///     // ...
+/// }
+/// ```
+#[macro_export]
+macro_rules! preintern_file {
+    ($path:expr) => {
+        $crate::preintern_list(::core::include_str!($path))
+    };
+}
+
+/// Returns whether a string has been interned, without interning it.
+///
+/// See [`Symbol::try_get`].
+pub fn is_interned(s: &str) -> bool {
+    Symbol::try_get(s).is_some()
+}
+
+/// Interns strings from a parallel iterator, using rayon's thread pool. Requires the `rayon`
+/// feature.
+///
+/// The strings are split into batches, and each batch is interned like
+/// [`Symbol::intern_all`], taking each of the interner's locks at most once, so threads interning
+/// long runs of new strings mostly contend only when they need the same shard at the same time.
+/// The symbols are returned in the order of the iterator.
+///
+/// ```
+/// use rayon::prelude::*;
+///
+/// let words = ["the", "quick", "brown", "the", "fox"];
+/// let syms = symbol::par_intern(words.par_iter().copied());
+/// assert_eq!(syms, words);
+/// assert_eq!(syms[0].addr(), syms[3].addr());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_intern<'a, I>(iter: I) -> Vec<Symbol>
+where
+    I: rayon::iter::IntoParallelIterator<Item = &'a str>,
+{
+    use rayon::prelude::*;
+
+    /// The number of strings in each batch.
+    const BATCH: usize = 1024;
+
+    // Find the interner here rather than on the pool's threads, which might not be isolated.
+    let heap = heap();
+    let strs = iter.into_par_iter().collect::<Vec<_>>();
+    strs.par_chunks(BATCH)
+        .flat_map_iter(|batch| heap.intern_all(batch))
+        .map(|data| Symbol { data })
+        .collect()
+}
+
+/// Interns each line read from a reader, such as a dictionary file, with the line ending removed.
+///
+/// Lines are read and interned in batches, as with [`Symbol::intern_all`], so loading a large file
+/// takes each of the interner's locks once per batch rather than once per line. The symbols are
+/// returned in the order their lines were read. If reading fails, the lines of the batches before
+/// it have already been interned.
+///
+/// ```
+/// let words = "apple\nbanana\r\ncherry\n";
+/// let syms = symbol::intern_lines(words.as_bytes()).unwrap();
+/// assert_eq!(syms, ["apple", "banana", "cherry"]);
+/// ```
+#[cfg(feature = "std")]
+pub fn intern_lines(reader: impl std::io::BufRead) -> std::io::Result<Vec<Symbol>> {
+    intern_batched(reader, |line, batch| batch.push(line.into()))
+}
+
+/// Interns each whitespace-separated word read from a reader.
+///
+/// This works like [`intern_lines`], but splits each line into words and skips blank lines.
+///
+/// ```
+/// let text = "let x =\n  x + 1\n";
+/// let syms = symbol::intern_words(text.as_bytes()).unwrap();
+/// assert_eq!(syms, ["let", "x", "=", "x", "+", "1"]);
+/// ```
+#[cfg(feature = "std")]
+pub fn intern_words(reader: impl std::io::BufRead) -> std::io::Result<Vec<Symbol>> {
+    intern_batched(reader, |line, batch| {
+        batch.extend(line.split_whitespace().map(String::from))
+    })
+}
+
+/// Reads lines, splits each into the strings to intern with `split`, and interns them a batch at a
+/// time.
+#[cfg(feature = "std")]
+fn intern_batched(
+    mut reader: impl std::io::BufRead,
+    mut split: impl FnMut(&str, &mut Vec<String>),
+) -> std::io::Result<Vec<Symbol>> {
+    /// The number of strings in each batch.
+    const BATCH: usize = 1024;
+
+    let heap = heap();
+    let mut out = Vec::new();
+    let mut batch = Vec::with_capacity(BATCH);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let done = reader.read_line(&mut line)? == 0;
+        if !done {
+            let trimmed = line.strip_suffix('\n').unwrap_or(&line);
+            split(trimmed.strip_suffix('\r').unwrap_or(trimmed), &mut batch);
+        }
+        if batch.len() >= BATCH || (done && !batch.is_empty()) {
+            let strs = batch.iter().map(|s| &**s).collect::<Vec<_>>();
+            out.extend(
+                heap.intern_all(&strs)
+                    .into_iter()
+                    .map(|data| Symbol { data }),
+            );
+            batch.clear();
+        }
+        if done {
+            return Ok(out);
+        }
+    }
+}
 
-        let mut heap = SYMBOL_HEAP.lock();
-        let n = loop {
-            let n = leak_string(format!("G#{}", N.fetch_add(1, AtomicOrdering::SeqCst)));
-            if heap.insert(n) {
-                break n;
+/// Freezes the interner, for programs that intern everything they need during a loading phase.
+///
+/// Afterwards, [`Symbol::try_intern`] fails for strings that were not already interned. Other ways
+/// of making symbols, such as `From` and [`Symbol::gensym`], keep working, so freezing never makes
+/// existing code panic. Lookups are unaffected, and never lock either way.
+pub fn freeze() {
+    heap().freeze();
+}
+
+/// Returns whether [`freeze`] has been called.
+pub fn is_frozen() -> bool {
+    heap().is_frozen()
+}
+
+/// Sets limits on what [`Symbol::try_intern`] will intern, to protect programs that intern
+/// strings from untrusted input from running out of memory.
+///
+/// ```
+/// # use symbol::{InternError, Limits, Symbol};
+/// symbol::set_limits(Limits {
+///     max_len: Some(64),
+///     ..Limits::default()
+/// });
+/// let long = "x".repeat(65);
+/// assert_eq!(Symbol::try_intern(&long), Err(InternError::TooLong));
+/// assert!(!symbol::is_interned(&long));
+/// ```
+pub fn set_limits(limits: Limits) {
+    heap().set_limits(limits);
+}
+
+/// Makes room in the interner for at least `additional` more symbols, so that interning them
+/// won't have to grow its tables.
+pub fn reserve(additional: usize) {
+    heap().reserve(additional);
+}
+
+/// Calls `observer` with each symbol that interning creates from now on, and the length of its
+/// string, replacing any observer that was set before.
+///
+/// This is for keeping metrics of your own, logging, or copying symbols somewhere else as they
+/// appear. The observer is only called when a string is interned for the first time, and not for
+/// gensyms or uninterned symbols. It runs on the thread that interned the string, after the
+/// interner has been unlocked, so it may intern strings itself; but each new one calls it again.
+/// A replaced observer is never dropped, since another thread might still be calling it.
+///
+/// ```
+/// # use symbol::Symbol;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static NEW_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// symbol::set_intern_observer(|_, len| {
+///     NEW_BYTES.fetch_add(len, Ordering::Relaxed);
+/// });
+/// let _ = Symbol::from("observed");
+/// let _ = Symbol::from("observed");
+/// assert_eq!(NEW_BYTES.load(Ordering::Relaxed), 8);
+/// symbol::clear_intern_observer();
+/// ```
+pub fn set_intern_observer(observer: impl Fn(Symbol, usize) + Send + Sync + 'static) {
+    heap().set_observer(Some(Box::new(move |data: &'static SymbolData| {
+        observer(Symbol { data }, data.as_str().len())
+    })));
+}
+
+/// Stops calling the observer set by [`set_intern_observer`].
+pub fn clear_intern_observer() {
+    heap().set_observer(None);
+}
+
+/// Removes every symbol from the interner, and frees the memory they use.
+///
+/// This is meant for test suites and fuzzers, which would otherwise keep every string they ever
+/// interned until the process exits. Ids start again from zero afterwards, and interning a string
+/// again gives a new symbol.
+///
+/// # Safety
+///
+/// Every existing symbol is invalidated, along with anything borrowed from one, such as the
+/// `&'static str` returned by [`Symbol::as_str`]. None of these may be used afterwards, not even
+/// to compare them, and no other thread may intern or look up symbols until this returns.
+/// [`Symbol32`]s and ids from before the call must not be used either, since they may now refer
+/// to different symbols.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let _ = Symbol::from("short-lived");
+/// assert!(symbol::is_interned("short-lived"));
+/// unsafe { symbol::unsafe_clear() };
+/// assert!(!symbol::is_interned("short-lived"));
+/// assert_eq!(symbol::stats().symbols, 0);
+/// ```
+pub unsafe fn unsafe_clear() {
+    #[cfg(feature = "thread-local-cache")]
+    cache::invalidate();
+    heap().clear();
+}
+
+/// Returns every symbol in the interner, along with the state of [`Symbol::gensym`], so that a
+/// later session can [`restore`] them with the same ids.
+///
+/// Gensyms and other symbols that aren't interned are left out, since they can't be looked up by
+/// name; their ids are left unused when the snapshot is restored.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let a = Symbol::from("saved");
+/// let snapshot = symbol::snapshot();
+/// assert!(snapshot.strings().any(|s| s == "saved"));
+///
+/// // Restoring into the same interner, or a new process's, gives the same ids.
+/// symbol::restore(&snapshot).unwrap();
+/// assert_eq!(Symbol::try_get("saved").map(Symbol::id), Some(a.id()));
+/// ```
+pub fn snapshot() -> InternerSnapshot {
+    InternerSnapshot {
+        strings: heap()
+            .entries()
+            .into_iter()
+            .map(|data| {
+                data.filter(|data| data.is_interned())
+                    .map(|data| data.as_str().into())
+            })
+            .collect(),
+        gensym: heap().gensym_counter(),
+    }
+}
+
+/// Interns every symbol from a [`snapshot`], so that each gets the id it had when the snapshot was
+/// taken, and makes sure [`Symbol::gensym`] won't reuse any of its names.
+///
+/// This succeeds if the symbols already interned are the first ones in the snapshot, in the same
+/// order; most usefully, when nothing has been interned yet. Otherwise some symbol can't get its
+/// old id, and this returns [`InternError::IdConflict`], leaving the symbols before it interned.
+/// It ignores [`freeze`].
+pub fn restore(snapshot: &InternerSnapshot) -> Result<(), InternError> {
+    restore_with(
+        snapshot.strings.iter().map(Option::as_deref),
+        snapshot.gensym,
+        |entry, s| entry.insert(s),
+    )
+}
+
+/// Restores the symbols with the given strings, in id order, inserting new ones with `insert`.
+fn restore_with<'a>(
+    strings: impl IntoIterator<Item = Option<&'a str>>,
+    gensym: usize,
+    insert: impl Fn(VacantEntry, &'a str) -> &'static SymbolData,
+) -> Result<(), InternError> {
+    for (id, s) in strings.into_iter().enumerate() {
+        let restored = match s {
+            Some(s) => {
+                let hash = heap().hash(s);
+                let data = match heap().entry(hash, s) {
+                    Entry::Occupied(data) => data,
+                    Entry::Vacant(entry) => insert(entry, s),
+                };
+                data.id()
             }
+            // The symbol was collected or uninterned. If the interner has this id already, it
+            // mustn't be interned there either.
+            None if (id as u32) < heap().id_count() => match heap().get_by_id(id as u32) {
+                Some(data) if data.is_interned() => return Err(InternError::IdConflict),
+                _ => id as u32,
+            },
+            None => heap().skip_id(),
         };
-        drop(heap);
+        if restored as usize != id {
+            return Err(InternError::IdConflict);
+        }
+    }
+    heap().advance_gensym(gensym);
+    Ok(())
+}
+
+/// Takes a checkpoint of the interner, which removes every symbol interned after it when dropped.
+///
+/// This lets a fuzzer or a server that handles one request at a time bound its memory use, by
+/// freeing the symbols each iteration interns when the iteration is done. Symbols interned before
+/// the checkpoint are unaffected.
+///
+/// # Safety
+///
+/// When the guard is dropped, every symbol interned since it was created is invalidated, as are
+/// their ids and anything borrowed from them, just as [`unsafe_clear`] invalidates every symbol.
+/// None of these may be used afterwards, and no other thread may intern or look up symbols while
+/// the guard is being dropped.
+///
+/// Guards must be dropped in the reverse of the order they were created in, and not after
+/// [`unsafe_clear`] or [`collect`] has been called. Forgetting a guard is allowed, and just keeps
+/// its symbols.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let kept = Symbol::from("kept");
+/// let guard = unsafe { symbol::checkpoint() };
+/// let _ = Symbol::from("temporary");
+/// assert!(symbol::is_interned("temporary"));
+/// drop(guard);
+/// assert!(!symbol::is_interned("temporary"));
+/// assert_eq!(Symbol::try_get("kept"), Some(kept));
+/// ```
+pub unsafe fn checkpoint() -> InternerGuard {
+    let interner = heap();
+    InternerGuard {
+        interner,
+        checkpoint: interner.checkpoint(),
+    }
+}
+
+/// A guard that rolls the interner back when dropped, from [`checkpoint`].
+#[must_use = "the interner is rolled back when the guard is dropped"]
+pub struct InternerGuard {
+    interner: &'static Interner,
+    checkpoint: Checkpoint,
+}
+
+impl Drop for InternerGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "thread-local-cache")]
+        cache::invalidate();
+        // SAFETY: The caller of `checkpoint` promised to meet the requirements.
+        unsafe { self.interner.rollback(&self.checkpoint) }
+    }
+}
+
+/// Removes every symbol the callback doesn't mark as live, and frees as much of their memory as
+/// it can.
+///
+/// This lets a long-running interpreter reclaim the symbols its programs no longer use, by
+/// marking the ones reachable from its roots (its environments, ASTs, and so on) every so often.
+/// Symbols interned while the callback runs are always kept. Collected symbols' ids are not handed
+/// out again, so [`Symbol::from_id`] returns `None` for them.
+///
+/// A symbol's string is stored next to those of other symbols interned around the same time, and
+/// that storage is only freed once all of them have been collected. Strings longer than a
+/// kilobyte have storage of their own, so they are always freed.
+///
+/// # Safety
+///
+/// Lookups in the interner don't take any lock, and don't announce themselves in any other way,
+/// so there is no way to tell when a thread might still be looking at an entry that is being
+/// removed. Instead, the caller promises that no other thread interns or looks up symbols until
+/// this returns, and afterwards, that no symbol the callback didn't mark is used, nor anything
+/// borrowed from one, as with [`unsafe_clear`]. This must also not be called while a
+/// [`checkpoint`] guard is alive.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let live = Symbol::from("live");
+/// let _ = Symbol::from("garbage");
+/// unsafe { symbol::collect(|marker| marker.mark(live)) };
+/// assert!(symbol::is_interned("live"));
+/// assert!(!symbol::is_interned("garbage"));
+/// ```
+pub unsafe fn collect(roots: impl FnOnce(&mut Marker)) {
+    let len = heap().id_count();
+    let mut marker = Marker {
+        marked: vec![0; (len as usize + 63) / 64],
+    };
+    roots(&mut marker);
+
+    #[cfg(feature = "thread-local-cache")]
+    cache::invalidate();
+    heap().retain(|data| data.id() >= len || marker.is_marked_id(data.id()));
+}
+
+/// The symbols marked as live during a [`collect`].
+pub struct Marker {
+    /// A bit for each id that existed when the collection started.
+    marked: Vec<u64>,
+}
+
+impl Marker {
+    /// Marks a symbol as live, so that it is kept.
+    pub fn mark(&mut self, sym: Symbol) {
+        let id = sym.id() as usize;
+        if let Some(word) = self.marked.get_mut(id / 64) {
+            *word |= 1 << (id % 64);
+        }
+    }
+
+    /// Returns whether a symbol has been marked already. Callbacks walking graphs of symbols can
+    /// use this to avoid visiting a symbol twice.
+    pub fn is_marked(&self, sym: Symbol) -> bool {
+        self.is_marked_id(sym.id())
+    }
+
+    fn is_marked_id(&self, id: u32) -> bool {
+        let id = id as usize;
+        self.marked
+            .get(id / 64)
+            .map_or(false, |word| word & (1 << (id % 64)) != 0)
+    }
+}
+
+/// Returns every symbol interned so far, in order of [id](Symbol::id).
+///
+/// This takes a snapshot of how many symbols there are when it is called, and doesn't lock, so
+/// symbols interned by other threads while iterating are not included, and one that another thread
+/// is part way through interning may be missed.
+///
+/// ```
+/// let sym = symbol::Symbol::from("enumerated");
+/// assert!(symbol::iter().any(|s| s == sym));
+/// ```
+pub fn iter() -> impl Iterator<Item = Symbol> {
+    heap().iter().map(|data| Symbol { data })
+}
+
+/// Returns every symbol interned so far that starts with the given prefix, in order of
+/// [id](Symbol::id).
+///
+/// The interner is a hash table, so this checks every symbol in turn, as [`iter`] would; collect
+/// and sort the results if they are needed in lexicographic order. Programs that look up
+/// prefixes often, such as for completion in a REPL, may be better served by keeping the symbols
+/// they care about in a trie, such as `radix_trie`'s (with the `radix_trie` feature).
+///
+/// ```
+/// let _ = symbol::Symbol::from("std::vec");
+/// let _ = symbol::Symbol::from("std::mem");
+/// let _ = symbol::Symbol::from("core::mem");
+/// let mut found = symbol::with_prefix("std::").collect::<Vec<_>>();
+/// found.sort_by_key(|s| s.as_str());
+/// assert_eq!(found, ["std::mem", "std::vec"]);
+/// ```
+pub fn with_prefix(prefix: &str) -> impl Iterator<Item = Symbol> + '_ {
+    iter().filter(move |s| s.as_str().starts_with(prefix))
+}
+
+/// Shrinks the interner's tables as much as possible, and frees the tables they replaced as they
+/// grew, for programs that are done interning after a loading phase, or that have just
+/// [`collect`]ed many symbols.
+///
+/// Symbols are unaffected. Strings take the same space either way; this only frees the tables for
+/// looking strings up, which are kept at most half full, and normally keep the tables they
+/// replaced alive so that lookups never need a lock.
+///
+/// # Safety
+///
+/// No other thread may intern or look up symbols until this returns. [`Interner::shrink_to_fit`]
+/// on an interner of one's own is safe, since it takes a unique reference.
+///
+/// ```
+/// symbol::preintern(&["a", "b", "c"]);
+/// let before = symbol::stats();
+/// unsafe { symbol::shrink_to_fit() };
+/// assert!(symbol::stats().table_bytes <= before.table_bytes);
+/// assert!(symbol::is_interned("b"));
+/// ```
+pub unsafe fn shrink_to_fit() {
+    heap().shrink_tables();
+}
+
+/// Returns how many symbols have been interned, and how much memory the interner is using.
+///
+/// Interned strings are never freed, so programs that intern strings from untrusted input can use
+/// this to watch how much memory they have committed.
+///
+/// ```
+/// let before = symbol::stats();
+/// let _ = symbol::Symbol::from("a string that has not been interned before");
+/// let after = symbol::stats();
+/// assert!(after.symbols > before.symbols);
+/// assert!(after.string_bytes >= before.string_bytes + 42);
+/// ```
+pub fn stats() -> Stats {
+    heap().stats()
+}
+
+/// Writes a line for every symbol, with its id, address, length, and string, for debugging.
+///
+/// This shows what [`iter`] would return, along with the symbols that aren't interned, like
+/// gensyms, which are marked as such. Two symbols that print the same but aren't equal show up
+/// as two lines with the same string. [`Dump`] does the same through its `Debug` impl.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let _ = Symbol::from("dumped");
+/// let mut out = String::new();
+/// symbol::dump(&mut out).unwrap();
+/// assert!(out.lines().any(|line| line.ends_with(" 6 \"dumped\"")));
+/// ```
+pub fn dump(w: &mut dyn std::fmt::Write) -> FmtResult {
+    write!(w, "{:?}", Dump)
+}
+
+/// The interner's symbols, which `Debug` formats as [`dump`] does.
+///
+/// ```
+/// println!("{:?}", symbol::Dump);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Dump;
+
+impl Debug for Dump {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        for data in heap().entries().into_iter().flatten() {
+            write!(
+                fmt,
+                "#{} {:#x} {} {:?}",
+                data.id(),
+                data.as_str().as_ptr() as usize,
+                data.as_str().len(),
+                data.as_str(),
+            )?;
+            if data.is_gensym() {
+                fmt.write_str(" (gensym)")?;
+            } else if !data.is_interned() {
+                fmt.write_str(" (uninterned)")?;
+            }
+            fmt.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns how often interning has found strings already present, and how often it has had to wait
+/// for a lock. Requires the `metrics` feature.
+///
+/// A high hit rate with frequent contention suggests that [`preintern`]ing the common strings at
+/// startup would help. Lookups answered by the `thread-local-cache` feature's cache never reach
+/// the interner, so they aren't counted.
+///
+/// ```
+/// let before = symbol::interner_metrics();
+/// let _ = symbol::Symbol::from("metrics");
+/// let after = symbol::interner_metrics();
+/// assert_eq!(after.misses, before.misses + 1);
+///
+/// symbol::preintern(&["preinterned"]);
+/// let before = symbol::interner_metrics();
+/// let _ = symbol::Symbol::from("preinterned");
+/// let after = symbol::interner_metrics();
+/// assert_eq!(after.hits, before.hits + 1);
+/// ```
+#[cfg(feature = "metrics")]
+pub fn interner_metrics() -> InternerMetrics {
+    heap().metrics()
+}
+
+/// An error from interning a string.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum InternError {
+    /// The string wasn't already interned, and the interner is frozen.
+    Frozen,
+
+    /// The string is longer than the interner's [`max_len`](Limits::max_len).
+    TooLong,
+
+    /// Interning the string would take the interner over its [`max_bytes`](Limits::max_bytes).
+    LimitReached,
+
+    /// A snapshot's symbol couldn't be given the id it had, because the interner already had a
+    /// different symbol with that id.
+    IdConflict,
+}
+
+impl Display for InternError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            InternError::Frozen => fmt.write_str("the interner is frozen"),
+            InternError::TooLong => fmt.write_str("the string is too long to intern"),
+            InternError::LimitReached => {
+                fmt.write_str("the interner has reached its limit on interned bytes")
+            }
+            InternError::IdConflict => {
+                fmt.write_str("a symbol could not be restored with the id it had before")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InternError {}
+
+/// Why a string isn't an identifier, from [`Symbol::new_ident`]. Requires the `unicode-ident`
+/// feature.
+#[cfg(feature = "unicode-ident")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum IdentError {
+    /// The string is empty.
+    Empty,
+
+    /// A character can't appear where it does in an identifier.
+    InvalidChar {
+        /// The byte offset of the character in the string.
+        offset: usize,
+
+        /// The character.
+        ch: char,
+    },
+}
+
+#[cfg(feature = "unicode-ident")]
+impl Display for IdentError {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match self {
+            IdentError::Empty => fmt.write_str("an identifier can't be empty"),
+            IdentError::InvalidChar { offset: 0, ch } => {
+                write!(fmt, "an identifier can't start with {:?}", ch)
+            }
+            IdentError::InvalidChar { offset, ch } => {
+                write!(
+                    fmt,
+                    "an identifier can't contain {:?} (at byte {})",
+                    ch, offset
+                )
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "std", feature = "unicode-ident"))]
+impl std::error::Error for IdentError {}
+
+/// Adding a string or a symbol to a symbol interns the concatenation, formatting it as
+/// [`from_fmt`](Symbol::from_fmt) does.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let base = Symbol::from("Widget");
+/// assert_eq!(base + "Impl", "WidgetImpl");
+/// assert_eq!("Abstract" + base, "AbstractWidget");
+/// assert_eq!(base + base, Symbol::from("WidgetWidget"));
+/// ```
+impl Add<&str> for Symbol {
+    type Output = Symbol;
 
-        Symbol::from(n)
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn add(self, rhs: &str) -> Symbol {
+        Symbol::from_fmt(format_args!("{}{}", self.as_str(), rhs))
     }
 }
 
+impl Add<Symbol> for Symbol {
+    type Output = Symbol;
+
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn add(self, rhs: Symbol) -> Symbol {
+        self + rhs.as_str()
+    }
+}
+
+impl Add<Symbol> for &str {
+    type Output = Symbol;
+
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn add(self, rhs: Symbol) -> Symbol {
+        Symbol::from_fmt(format_args!("{}{}", self, rhs.as_str()))
+    }
+}
+
+/// This is also what lets wrappers that compare strings case-insensitively, such as the `unicase`
+/// crate's `UniCase<Symbol>` and `Ascii<Symbol>`, hold symbols. They compare the strings on
+/// every lookup; [`CiSymbol`] folds case once, when it is interned.
+///
+/// ```ignore
+/// use symbol::Symbol;
+/// use unicase::UniCase;
+///
+/// let mut headers = std::collections::HashMap::new();
+/// headers.insert(UniCase::new(Symbol::from("Content-Type")), "text/plain");
+/// assert_eq!(headers[&UniCase::new(Symbol::from("content-type"))], "text/plain");
+/// ```
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for Symbol {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// This lets a symbol be passed straight to functions that take paths or command-line arguments.
+///
+/// ```no_run
+/// # use symbol::Symbol;
+/// let program = Symbol::from("rustc");
+/// let path = Symbol::from("main.rs");
+/// let _ = std::fs::File::open(path);
+/// let _ = std::process::Command::new(program).arg(path).status();
+/// ```
+#[cfg(feature = "std")]
+impl AsRef<std::ffi::OsStr> for Symbol {
+    fn as_ref(&self) -> &std::ffi::OsStr {
+        self.as_str().as_ref()
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsRef<std::path::Path> for Symbol {
+    fn as_ref(&self) -> &std::path::Path {
+        self.as_str().as_ref()
+    }
+}
+
+/// Symbols are debugged as their strings. The alternate form, `{:#?}`, adds the symbol's id, to
+/// tell apart symbols with the same string, such as gensyms, or symbols from different interners.
+/// Since `{:#?}` is passed down to fields, this also applies to symbols in pretty-printed structs.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let s = Symbol::from("foo");
+/// assert_eq!(format!("{:?}", s), r#""foo""#);
+/// assert_eq!(format!("{:#?}", s), format!(r#""foo" @ #{}"#, s.id()));
+/// ```
 impl Debug for Symbol {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
-        Debug::fmt(self.s, fmt)
+        Debug::fmt(self.as_str(), fmt)?;
+        if fmt.alternate() {
+            write!(fmt, " @ #{}", self.id())?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn default() -> Symbol {
+        Symbol::empty()
     }
 }
 
 impl Deref for Symbol {
     type Target = str;
     fn deref(&self) -> &str {
-        self.s
+        self.as_str()
     }
 }
 
+/// Symbols are displayed as their strings, padded and truncated as `str`s are.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let s = Symbol::from("name");
+/// assert_eq!(format!("[{:>6}]", s), "[  name]");
+/// assert_eq!(format!("[{:-<6.2}]", s), "[na----]");
+/// ```
 impl Display for Symbol {
     fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
-        fmt.write_str(self.s)
+        fmt.pad(self.as_str())
     }
 }
 
-impl<S: AsRef<str>> From<S> for Symbol {
-    fn from(s: S) -> Symbol {
-        let s = s.as_ref();
-        {
-            let mut heap = SYMBOL_HEAP.lock();
-            if heap.get(s).is_none() {
-                heap.insert(leak_string(s.to_owned()));
-            }
+impl<'a> From<&'a str> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(s: &'a str) -> Symbol {
+        #[cfg(feature = "thread-local-cache")]
+        if let Some(data) = cache::get(s) {
+            return Symbol { data };
         }
-        let s = {
-            let heap = SYMBOL_HEAP.lock();
-            *heap.get(s).unwrap()
+
+        let hash = heap().hash(s);
+        let data = match heap().entry(hash, s) {
+            Entry::Occupied(data) => data,
+            Entry::Vacant(entry) => entry.insert(s),
         };
-        Symbol { s }
+        #[cfg(feature = "thread-local-cache")]
+        cache::put(data);
+        Symbol { data }
+    }
+}
+
+/// The character is encoded on the stack, so this doesn't allocate if the symbol already exists.
+///
+/// ```
+/// # use symbol::Symbol;
+/// assert_eq!(Symbol::from('+'), "+");
+/// assert_eq!(Symbol::from('λ'), Symbol::from("λ"));
+/// ```
+impl From<char> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(c: char) -> Symbol {
+        Symbol::from(&*c.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<'a> From<&'a String> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(s: &'a String) -> Symbol {
+        Symbol::from(s.as_str())
+    }
+}
+
+impl From<String> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(s: String) -> Symbol {
+        Symbol::intern_owned(s)
+    }
+}
+
+/// Like `From<String>`, this keeps the string's buffer if it needs one of its own.
+impl From<Box<str>> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(s: Box<str>) -> Symbol {
+        Symbol::intern_owned(s.into())
     }
 }
 
+/// Owned strings are interned like `String`s, and borrowed ones like `&str`s.
+impl<'a> From<Cow<'a, str>> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(s: Cow<'a, str>) -> Symbol {
+        match s {
+            Cow::Borrowed(s) => Symbol::from(s),
+            Cow::Owned(s) => Symbol::intern_owned(s),
+        }
+    }
+}
+
+/// If the string is new and long enough to need an allocation of its own, the interner keeps a
+/// reference to the `Arc` instead of copying it.
+///
+/// ```
+/// # use symbol::Symbol;
+/// # use std::sync::Arc;
+/// let long: Arc<str> = "y".repeat(4096).into();
+/// let sym = Symbol::from(long.clone());
+/// assert_eq!(sym.as_str().as_ptr(), long.as_ptr());
+/// ```
+impl From<Arc<str>> for Symbol {
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from(s: Arc<str>) -> Symbol {
+        #[cfg(feature = "thread-local-cache")]
+        if let Some(data) = cache::get(&s) {
+            return Symbol { data };
+        }
+
+        let hash = heap().hash(&s);
+        let data = match heap().entry(hash, &s) {
+            Entry::Occupied(data) => data,
+            Entry::Vacant(entry) => entry.insert_shared(s),
+        };
+        #[cfg(feature = "thread-local-cache")]
+        cache::put(data);
+        Symbol { data }
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(sym: Symbol) -> String {
+        String::from(sym.as_str())
+    }
+}
+
+/// Since a symbol's string is `'static`, this borrows it rather than copying it.
+///
+/// ```
+/// # use symbol::Symbol;
+/// use std::borrow::Cow;
+///
+/// let s = Symbol::from("cow");
+/// assert!(matches!(Cow::from(s), Cow::Borrowed("cow")));
+/// ```
+impl From<Symbol> for Cow<'static, str> {
+    fn from(sym: Symbol) -> Cow<'static, str> {
+        Cow::Borrowed(sym.as_str())
+    }
+}
+
+impl From<Symbol> for Box<str> {
+    fn from(sym: Symbol) -> Box<str> {
+        Box::from(sym.as_str())
+    }
+}
+
+impl From<Symbol> for Arc<str> {
+    fn from(sym: Symbol) -> Arc<str> {
+        Arc::from(sym.as_str())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Symbol {
+    type Error = Utf8Error;
+
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn try_from(bytes: &'a [u8]) -> Result<Symbol, Utf8Error> {
+        Symbol::from_utf8(bytes)
+    }
+}
+
+/// Parsing a string interns it with [`Symbol::try_intern`], so it fails if the interner is frozen
+/// or the string is over its limits. This makes `parse` a good fit for strings from outside the
+/// program, such as command-line arguments and configuration.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let s: Symbol = "parsed".parse().unwrap();
+/// assert_eq!(s, "parsed");
+/// ```
+impl FromStr for Symbol {
+    type Err = InternError;
+
+    #[cfg_attr(feature = "debug-intern-sites", track_caller)]
+    fn from_str(s: &str) -> Result<Symbol, InternError> {
+        Symbol::try_intern(s)
+    }
+}
+
+impl Eq for Symbol {}
+
+/// Symbols hash a 64-bit hash of their string that was computed when it was interned, so hashing
+/// one costs the same no matter how long the string is. This means a symbol doesn't hash the same
+/// as its string. The hash of a string is the same on every run of a program.
+///
+/// For this reason, and because a gensym isn't equal to the interned symbol with the same name,
+/// `Symbol` doesn't implement `Borrow<str>`, and `str` doesn't implement `hashbrown`'s or
+/// `indexmap`'s `Equivalent<Symbol>`: maps keyed by symbols would find the wrong entries if they
+/// were looked up by strings. To look up a string without interning it, use [`Symbol::try_get`],
+/// which returns `None` for a string that can't be a key because it was never interned. In the
+/// other direction, a map keyed by strings can be looked up by a symbol's
+/// [`as_str`](Symbol::as_str).
+///
+/// ```
+/// # use symbol::Symbol;
+/// use std::collections::HashMap;
+///
+/// let mut arities = HashMap::new();
+/// arities.insert(Symbol::from("cons"), 2);
+/// let arity = |name: &str| Symbol::try_get(name).and_then(|sym| arities.get(&sym).copied());
+/// assert_eq!(arity("cons"), Some(2));
+/// assert_eq!(arity("never interned"), None);
+/// assert!(!symbol::is_interned("never interned"));
+/// ```
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.data.hash())
+    }
+}
+
+/// Symbols are ordered by their [ids](Symbol::id), which is the order they were interned in. A
+/// program that interns the same strings in the same order on every run sorts its symbols the same
+/// way every time, unlike their [addresses](Symbol::addr), which can change from run to run. This
+/// isn't the order of their strings, which [`Symbol::cmp_by_str`] compares instead.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let b = Symbol::from("b");
+/// let a = Symbol::from("a");
+/// assert!(b < a);
+/// ```
 impl Ord for Symbol {
     fn cmp(&self, other: &Self) -> Ordering {
-        let l = self.addr();
-        let r = other.addr();
-        l.cmp(&r)
+        self.cmp_by_id(other)
     }
 }
 
 impl PartialEq for Symbol {
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other) == Ordering::Equal
+        std::ptr::eq(self.data, other.data)
     }
 }
 
@@ -168,18 +2113,40 @@ impl PartialOrd for Symbol {
     }
 }
 
-impl<S: AsRef<str>> PartialEq<S> for Symbol {
-    fn eq(&self, other: &S) -> bool {
-        self.partial_cmp(&other.as_ref()) == Some(Ordering::Equal)
-    }
-}
+/// Implements equality between symbols and a string type, in both directions, which compares the
+/// symbol's string.
+///
+/// There is deliberately no ordering between symbols and strings: it would have to compare strings,
+/// which disagrees with how symbols are ordered among themselves, and a collection that mixed the
+/// two orders would be corrupted. Compare [`as_str`](Symbol::as_str) with the string instead.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let s = Symbol::from("either way");
+/// assert_eq!(s, "either way");
+/// assert_eq!("either way", s);
+/// assert_eq!(String::from("either way"), s);
+/// ```
+///
+/// These can't be a single impl for every `S: AsRef<str>`, since `Symbol` is one of those.
+macro_rules! impl_eq_str {
+    ($($ty:ty),*) => {$(
+        impl PartialEq<$ty> for Symbol {
+            fn eq(&self, other: &$ty) -> bool {
+                self.as_str() == AsRef::<str>::as_ref(other)
+            }
+        }
 
-impl<S: AsRef<str>> PartialOrd<S> for Symbol {
-    fn partial_cmp(&self, other: &S) -> Option<Ordering> {
-        self.s.partial_cmp(other.as_ref())
-    }
+        impl PartialEq<Symbol> for $ty {
+            fn eq(&self, other: &Symbol) -> bool {
+                AsRef::<str>::as_ref(self) == other.as_str()
+            }
+        }
+    )*};
 }
 
+impl_eq_str!(str, &str, String, Cow<'_, str>);
+
 #[cfg(feature = "gc")]
 impl ::gc::Finalize for Symbol {
     fn finalize(&self) {}
@@ -190,6 +2157,9 @@ unsafe impl ::gc::Trace for Symbol {
     unsafe_empty_trace!();
 }
 
+#[cfg(feature = "nohash-hasher")]
+impl nohash_hasher::IsEnabled for Symbol {}
+
 #[cfg(feature = "radix_trie")]
 impl radix_trie::TrieKey for Symbol {
     fn encode_bytes(&self) -> Vec<u8> {
@@ -197,15 +2167,16 @@ impl radix_trie::TrieKey for Symbol {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize(ser)
+    }
+}
+
 #[cfg(feature = "serde")]
 impl<'de> serde::Deserialize<'de> for Symbol {
     fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Symbol, D::Error> {
         String::deserialize(de).map(Symbol::from)
     }
 }
-
-fn leak_string(s: String) -> &'static str {
-    let out = unsafe { transmute(&s as &str) };
-    forget(s);
-    out
-}
@@ -0,0 +1,99 @@
+//! Symbols from interners other than the global one.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+use crate::interner::SymbolData;
+
+/// An interned string from an [`Interner`](crate::Interner), which it borrows.
+///
+/// These work like [`Symbol`](crate::Symbol)s, but are only equal to symbols from the same
+/// interner: the same string interned in two interners gives two different symbols.
+#[derive(Clone, Copy)]
+pub struct LocalSymbol<'a> {
+    data: &'a SymbolData,
+}
+
+impl<'a> LocalSymbol<'a> {
+    pub(crate) fn new(data: &'a SymbolData) -> LocalSymbol<'a> {
+        LocalSymbol { data }
+    }
+
+    /// Returns the address of the interned string, which is unique among the symbols of its
+    /// interner.
+    pub fn addr(self) -> usize {
+        self.as_str().as_ptr() as usize
+    }
+
+    /// Retrieves the string, which lives as long as the interner.
+    pub fn as_str(self) -> &'a str {
+        self.data.as_str()
+    }
+
+    /// Returns the symbol's id, which is unique among the symbols of its interner.
+    pub fn id(self) -> u32 {
+        self.data.id()
+    }
+}
+
+impl<'a> Debug for LocalSymbol<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(self.as_str(), fmt)
+    }
+}
+
+impl<'a> Deref for LocalSymbol<'a> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> Display for LocalSymbol<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.pad(self.as_str())
+    }
+}
+
+impl<'a> Eq for LocalSymbol<'a> {}
+
+/// As with [`Symbol`](crate::Symbol), this hashes the hash computed when the string was interned.
+impl<'a> Hash for LocalSymbol<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.data.hash());
+    }
+}
+
+/// As with [`Symbol`](crate::Symbol), these are ordered by id, and so in the order they were
+/// interned in.
+impl<'a> Ord for LocalSymbol<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // As in `Symbol::cmp_by_id`, ties are broken by entry, which is what `==` compares.
+        self.id()
+            .cmp(&other.id())
+            .then_with(|| (self.data as *const SymbolData).cmp(&(other.data as *const SymbolData)))
+    }
+}
+
+impl<'a> PartialEq for LocalSymbol<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.data, other.data)
+    }
+}
+
+impl<'a> PartialOrd for LocalSymbol<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, S: AsRef<str>> PartialEq<S> for LocalSymbol<'a> {
+    fn eq(&self, other: &S) -> bool {
+        self.as_str() == other.as_ref()
+    }
+}
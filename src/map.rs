@@ -0,0 +1,422 @@
+//! Hash maps and sets keyed by symbols, which use the hash computed when each symbol was interned.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Index;
+
+use hashbrown::{hash_map, hash_set, HashMap, HashSet};
+
+use crate::{Symbol, SymbolMatcher};
+
+/// Builds [`SymbolHasher`]s, for keying other hash tables by symbols.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use symbol::{BuildSymbolHasher, Symbol};
+/// let mut map = HashMap::with_hasher(BuildSymbolHasher::default());
+/// map.insert(Symbol::from("k"), 1);
+/// assert_eq!(map[&Symbol::from("k")], 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildSymbolHasher;
+
+impl BuildHasher for BuildSymbolHasher {
+    type Hasher = SymbolHasher;
+    fn build_hasher(&self) -> SymbolHasher {
+        SymbolHasher(0)
+    }
+}
+
+/// A hasher that passes the hash a symbol was interned with through unchanged, so that hashing
+/// a symbol costs nothing.
+///
+/// These hashes aren't randomly seeded, so tables keyed by symbols made from an attacker's strings
+/// can be slowed down by collisions, as described under [`Limits`](crate::Limits).
+///
+/// Symbols' hashes are already well mixed, but anything else written to it, such as the length of a
+/// slice of symbols or an integer paired with one, is mixed in as it is. Keys whose hashes mostly
+/// come from other things are better off with another hasher.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use symbol::{BuildSymbolHasher, Symbol};
+/// let mut map = HashMap::with_hasher(BuildSymbolHasher::default());
+/// map.insert((Symbol::from("f"), 2u32), "f/2");
+/// map.insert((Symbol::from("f"), 3u32), "f/3");
+/// assert_eq!(map[&(Symbol::from("f"), 3)], "f/3");
+///
+/// let mut paths = HashMap::with_hasher(BuildSymbolHasher::default());
+/// paths.insert(vec![Symbol::from("std"), Symbol::from("mem")], 1);
+/// assert_eq!(paths[&[Symbol::from("std"), Symbol::from("mem")][..]], 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SymbolHasher(u64);
+
+impl Hasher for SymbolHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte.into());
+        }
+    }
+
+    fn write_u8(&mut self, n: u8) {
+        self.write_u64(n.into())
+    }
+
+    fn write_u16(&mut self, n: u16) {
+        self.write_u64(n.into())
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n.into())
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        // Mix in rather than overwrite, so that tuples of symbols hash all of them.
+        self.0 = self.0.rotate_left(5) ^ hash;
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64)
+    }
+}
+
+/// An entry in a [`SymbolMap`], from [`SymbolMap::entry`].
+pub type SymbolMapEntry<'a, V> = hash_map::Entry<'a, Symbol, V, BuildSymbolHasher>;
+
+/// A hash map keyed by symbols.
+///
+/// Symbols hash to the hash computed when they were interned, and compare by address, so looking
+/// a key up never touches its string, unlike with a `HashMap<Symbol, V>` using the default
+/// hasher, which hashes that hash again.
+///
+/// ```
+/// # use symbol::{Symbol, SymbolMap};
+/// let mut arities = SymbolMap::new();
+/// arities.insert(Symbol::from("cons"), 2);
+/// *arities.entry(Symbol::from("car")).or_insert(0) += 1;
+/// assert_eq!(arities[Symbol::from("cons")], 2);
+/// assert_eq!(arities.get(Symbol::from("car")), Some(&1));
+/// assert_eq!(arities.len(), 2);
+/// ```
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct SymbolMap<V> {
+    map: HashMap<Symbol, V, BuildSymbolHasher>,
+}
+
+impl<V> SymbolMap<V> {
+    /// Creates an empty map, which doesn't allocate until something is inserted.
+    pub fn new() -> SymbolMap<V> {
+        SymbolMap::with_capacity(0)
+    }
+
+    /// Creates an empty map with room for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> SymbolMap<V> {
+        SymbolMap {
+            map: HashMap::with_capacity_and_hasher(capacity, BuildSymbolHasher),
+        }
+    }
+
+    /// Returns the number of entries the map can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes every entry, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Makes room for at least `additional` more entries.
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional)
+    }
+
+    /// Inserts an entry, returning the value it replaced, if any.
+    pub fn insert(&mut self, key: Symbol, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    /// Returns the value for a key.
+    pub fn get(&self, key: Symbol) -> Option<&V> {
+        self.map.get(&key)
+    }
+
+    /// Returns the value for a key, mutably.
+    pub fn get_mut(&mut self, key: Symbol) -> Option<&mut V> {
+        self.map.get_mut(&key)
+    }
+
+    /// Returns whether there is an entry for a key.
+    pub fn contains_key(&self, key: Symbol) -> bool {
+        self.map.contains_key(&key)
+    }
+
+    /// Removes the entry for a key, returning its value.
+    pub fn remove(&mut self, key: Symbol) -> Option<V> {
+        self.map.remove(&key)
+    }
+
+    /// Returns the entry for a key, for inserting or updating it in place.
+    pub fn entry(&mut self, key: Symbol) -> SymbolMapEntry<'_, V> {
+        self.map.entry(key)
+    }
+
+    /// Removes every entry for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(Symbol, &mut V) -> bool) {
+        self.map.retain(|&key, value| keep(key, value))
+    }
+
+    /// Iterates over the entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &V)> + '_ {
+        self.map.iter().map(|(&key, value)| (key, value))
+    }
+
+    /// Iterates over the entries, in no particular order, with the values borrowed mutably.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Symbol, &mut V)> + '_ {
+        self.map.iter_mut().map(|(&key, value)| (key, value))
+    }
+
+    /// Iterates over the keys, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.map.keys().copied()
+    }
+
+    /// Iterates over the values, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.map.values()
+    }
+
+    /// Iterates over the values, in no particular order, borrowing them mutably.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+        self.map.values_mut()
+    }
+}
+
+impl<V: Debug> Debug for SymbolMap<V> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.map, fmt)
+    }
+}
+
+/// Reserves room for the entries before inserting them, as far as the iterator's size hint says.
+impl<V> Extend<(Symbol, V)> for SymbolMap<V> {
+    fn extend<I: IntoIterator<Item = (Symbol, V)>>(&mut self, iter: I) {
+        self.map.extend(iter)
+    }
+}
+
+impl<V> FromIterator<(Symbol, V)> for SymbolMap<V> {
+    fn from_iter<I: IntoIterator<Item = (Symbol, V)>>(iter: I) -> SymbolMap<V> {
+        let mut map = SymbolMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+/// Returns the value for a key.
+///
+/// # Panics
+///
+/// Panics if there is no entry for the key.
+impl<V> Index<Symbol> for SymbolMap<V> {
+    type Output = V;
+    fn index(&self, key: Symbol) -> &V {
+        &self.map[&key]
+    }
+}
+
+impl<V> IntoIterator for SymbolMap<V> {
+    type Item = (Symbol, V);
+    type IntoIter = hash_map::IntoIter<Symbol, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a SymbolMap<V> {
+    type Item = (&'a Symbol, &'a V);
+    type IntoIter = hash_map::Iter<'a, Symbol, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+/// A hash set of symbols, which hashes them as [`SymbolMap`] does.
+///
+/// ```
+/// # use symbol::{Symbol, SymbolSet};
+/// let free = "x y z".split(' ').collect::<SymbolSet>();
+/// let bound = "y".split(' ').collect::<SymbolSet>();
+/// let mut unbound = free.difference(&bound).collect::<Vec<_>>();
+/// unbound.sort_by(Symbol::cmp_by_str);
+/// assert_eq!(unbound, ["x", "z"]);
+/// assert!(bound.is_subset(&free));
+/// ```
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct SymbolSet {
+    set: HashSet<Symbol, BuildSymbolHasher>,
+}
+
+impl SymbolSet {
+    /// Creates an empty set, which doesn't allocate until something is inserted.
+    pub fn new() -> SymbolSet {
+        SymbolSet::with_capacity(0)
+    }
+
+    /// Creates an empty set with room for at least `capacity` symbols.
+    pub fn with_capacity(capacity: usize) -> SymbolSet {
+        SymbolSet {
+            set: HashSet::with_capacity_and_hasher(capacity, BuildSymbolHasher),
+        }
+    }
+
+    /// Returns the number of symbols the set can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.set.capacity()
+    }
+
+    /// Returns the number of symbols.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns whether there are no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Removes every symbol, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.set.clear()
+    }
+
+    /// Makes room for at least `additional` more symbols.
+    pub fn reserve(&mut self, additional: usize) {
+        self.set.reserve(additional)
+    }
+
+    /// Adds a symbol, returning whether it was new.
+    pub fn insert(&mut self, sym: Symbol) -> bool {
+        self.set.insert(sym)
+    }
+
+    /// Returns whether the set contains a symbol.
+    pub fn contains(&self, sym: Symbol) -> bool {
+        self.set.contains(&sym)
+    }
+
+    /// Removes a symbol, returning whether it was present.
+    pub fn remove(&mut self, sym: Symbol) -> bool {
+        self.set.remove(&sym)
+    }
+
+    /// Removes every symbol for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(Symbol) -> bool) {
+        self.set.retain(|&sym| keep(sym))
+    }
+
+    /// Iterates over the symbols, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.set.iter().copied()
+    }
+
+    /// Iterates over the symbols in either set.
+    pub fn union<'a>(&'a self, other: &'a SymbolSet) -> impl Iterator<Item = Symbol> + 'a {
+        self.set.union(&other.set).copied()
+    }
+
+    /// Iterates over the symbols in both sets.
+    pub fn intersection<'a>(&'a self, other: &'a SymbolSet) -> impl Iterator<Item = Symbol> + 'a {
+        self.set.intersection(&other.set).copied()
+    }
+
+    /// Iterates over the symbols in this set but not the other.
+    pub fn difference<'a>(&'a self, other: &'a SymbolSet) -> impl Iterator<Item = Symbol> + 'a {
+        self.set.difference(&other.set).copied()
+    }
+
+    /// Iterates over the symbols in exactly one of the sets.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a SymbolSet,
+    ) -> impl Iterator<Item = Symbol> + 'a {
+        self.set.symmetric_difference(&other.set).copied()
+    }
+
+    /// Returns whether every symbol in this set is also in the other.
+    pub fn is_subset(&self, other: &SymbolSet) -> bool {
+        self.set.is_subset(&other.set)
+    }
+
+    /// Returns whether the sets have no symbols in common.
+    pub fn is_disjoint(&self, other: &SymbolSet) -> bool {
+        self.set.is_disjoint(&other.set)
+    }
+
+    /// Builds an automaton that finds every occurrence of the set's symbols in some text.
+    pub fn matcher(&self) -> SymbolMatcher {
+        SymbolMatcher::new(self.iter())
+    }
+}
+
+impl Debug for SymbolSet {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.set, fmt)
+    }
+}
+
+impl Extend<Symbol> for SymbolSet {
+    fn extend<I: IntoIterator<Item = Symbol>>(&mut self, iter: I) {
+        self.set.extend(iter)
+    }
+}
+
+impl FromIterator<Symbol> for SymbolSet {
+    fn from_iter<I: IntoIterator<Item = Symbol>>(iter: I) -> SymbolSet {
+        let mut set = SymbolSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Interns the strings with [`Symbol::intern_all`], so each of the interner's locks is taken at
+/// most once.
+impl<'a> FromIterator<&'a str> for SymbolSet {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> SymbolSet {
+        Symbol::intern_all(iter).into_iter().collect()
+    }
+}
+
+impl IntoIterator for SymbolSet {
+    type Item = Symbol;
+    type IntoIter = hash_set::IntoIter<Symbol>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.set.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SymbolSet {
+    type Item = &'a Symbol;
+    type IntoIter = hash_set::Iter<'a, Symbol>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.set.iter()
+    }
+}
@@ -0,0 +1,233 @@
+//! Finding every occurrence of a set of symbols in text at once, with an Aho-Corasick automaton.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::{collections::VecDeque, vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+
+use std::ops::Range;
+
+use crate::Symbol;
+
+/// The state the automaton starts in, at the root of the trie.
+const ROOT: u32 = 0;
+
+/// An automaton that finds the symbols of a [`SymbolSet`](crate::SymbolSet) wherever they occur in
+/// some text, from [`SymbolSet::matcher`](crate::SymbolSet::matcher).
+///
+/// Scanning takes time proportional to the length of the text plus the number of matches, however
+/// many symbols there are. Every occurrence is reported, including overlapping ones, in the order
+/// they end. The empty symbol is never reported.
+///
+/// ```
+/// # use symbol::{Symbol, SymbolSet};
+/// let matcher = ["he", "she", "hers"].into_iter().collect::<SymbolSet>().matcher();
+/// let found = matcher
+///     .find_iter("ushers")
+///     .map(|m| (m.symbol().as_str(), m.start()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(found, [("she", 1), ("he", 2), ("hers", 2)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SymbolMatcher {
+    states: Vec<State>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct State {
+    /// The transitions out of this state in the trie, sorted by byte.
+    next: Vec<(u8, u32)>,
+
+    /// The state for the longest proper suffix of this state's string that is in the trie.
+    fail: u32,
+
+    /// The nearest state along the failure links that ends a symbol, or the root if none does.
+    output_link: u32,
+
+    /// The symbol whose string ends at this state, if there is one.
+    output: Option<Symbol>,
+
+    /// The length of this state's string.
+    depth: usize,
+}
+
+impl State {
+    fn next(&self, byte: u8) -> Option<u32> {
+        self.next
+            .binary_search_by_key(&byte, |&(b, _)| b)
+            .ok()
+            .map(|i| self.next[i].1)
+    }
+}
+
+impl SymbolMatcher {
+    /// Builds an automaton that finds the given symbols.
+    pub fn new(syms: impl IntoIterator<Item = Symbol>) -> SymbolMatcher {
+        let mut states = vec![State::default()];
+        for sym in syms {
+            if sym.is_empty() {
+                continue;
+            }
+            let mut state = ROOT;
+            for (depth, &byte) in sym.as_bytes().iter().enumerate() {
+                state = match states[state as usize].next(byte) {
+                    Some(next) => next,
+                    None => {
+                        let next = states.len() as u32;
+                        states.push(State {
+                            depth: depth + 1,
+                            ..State::default()
+                        });
+                        let transitions = &mut states[state as usize].next;
+                        let i = transitions.partition_point(|&(b, _)| b < byte);
+                        transitions.insert(i, (byte, next));
+                        next
+                    }
+                };
+            }
+            states[state as usize].output = Some(sym);
+        }
+
+        // Fill in the links breadth-first, so that each state's failure state, which is shallower,
+        // is done before it.
+        let mut queue = states[ROOT as usize]
+            .next
+            .iter()
+            .map(|&(_, child)| child)
+            .collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            for i in 0..states[state as usize].next.len() {
+                let (byte, child) = states[state as usize].next[i];
+                let mut fail = states[state as usize].fail;
+                let fail = loop {
+                    if let Some(next) = states[fail as usize].next(byte) {
+                        break next;
+                    }
+                    if fail == ROOT {
+                        break ROOT;
+                    }
+                    fail = states[fail as usize].fail;
+                };
+                let failed_to = &states[fail as usize];
+                let output_link = if failed_to.output.is_some() {
+                    fail
+                } else {
+                    failed_to.output_link
+                };
+                states[child as usize].fail = fail;
+                states[child as usize].output_link = output_link;
+                queue.push_back(child);
+            }
+        }
+        SymbolMatcher { states }
+    }
+
+    /// Iterates over every occurrence of the symbols in some text, which can be a `str` or bytes.
+    pub fn find_iter<'a, H>(&'a self, haystack: &'a H) -> FindIter<'a>
+    where
+        H: AsRef<[u8]> + ?Sized,
+    {
+        FindIter {
+            matcher: self,
+            haystack: haystack.as_ref(),
+            pos: 0,
+            state: ROOT,
+            report: ROOT,
+        }
+    }
+
+    /// Returns whether any of the symbols occurs in some text.
+    pub fn is_match<H: AsRef<[u8]> + ?Sized>(&self, haystack: &H) -> bool {
+        self.find_iter(haystack).next().is_some()
+    }
+
+    /// Returns the state after reading a byte in a state.
+    fn step(&self, mut state: u32, byte: u8) -> u32 {
+        loop {
+            if let Some(next) = self.states[state as usize].next(byte) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.states[state as usize].fail;
+        }
+    }
+}
+
+/// An occurrence of a symbol found by a [`SymbolMatcher`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SymbolMatch {
+    sym: Symbol,
+    start: usize,
+    end: usize,
+}
+
+impl SymbolMatch {
+    /// Returns the symbol that was found.
+    pub fn symbol(self) -> Symbol {
+        self.sym
+    }
+
+    /// Returns the offset of the first byte of the occurrence.
+    pub fn start(self) -> usize {
+        self.start
+    }
+
+    /// Returns the offset just past the last byte of the occurrence.
+    pub fn end(self) -> usize {
+        self.end
+    }
+
+    /// Returns the range of bytes the occurrence covers.
+    pub fn range(self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// The occurrences of a [`SymbolMatcher`]'s symbols in some text, from
+/// [`SymbolMatcher::find_iter`].
+#[derive(Clone, Debug)]
+pub struct FindIter<'a> {
+    matcher: &'a SymbolMatcher,
+    haystack: &'a [u8],
+
+    /// The offset of the next byte to read.
+    pos: usize,
+
+    /// The state after reading the bytes before `pos`.
+    state: u32,
+
+    /// The next state whose symbol, if any, ends at `pos`, or the root once all have been
+    /// reported.
+    report: u32,
+}
+
+impl<'a> Iterator for FindIter<'a> {
+    type Item = SymbolMatch;
+
+    fn next(&mut self) -> Option<SymbolMatch> {
+        loop {
+            if self.report != ROOT {
+                let state = &self.matcher.states[self.report as usize];
+                self.report = state.output_link;
+                if let Some(sym) = state.output {
+                    return Some(SymbolMatch {
+                        sym,
+                        start: self.pos - state.depth,
+                        end: self.pos,
+                    });
+                }
+                continue;
+            }
+
+            let &byte = self.haystack.get(self.pos)?;
+            self.state = self.matcher.step(self.state, byte);
+            self.pos += 1;
+            self.report = self.state;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Counters for how often the interner finds strings already present, and how often it has to wait
+//! for a lock.
+
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+/// One shard's counters. Keeping them per shard spreads the increments over as many cache lines as
+/// the lookups themselves touch.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    contended: AtomicUsize,
+}
+
+impl Metrics {
+    pub const fn new() -> Metrics {
+        Metrics {
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            contended: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn contended(&self) {
+        self.contended.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Adds this shard's counters to a snapshot.
+    pub fn add_to(&self, snapshot: &mut InternerMetrics) {
+        snapshot.hits += self.hits.load(Ordering::Relaxed);
+        snapshot.misses += self.misses.load(Ordering::Relaxed);
+        snapshot.contended += self.contended.load(Ordering::Relaxed);
+    }
+}
+
+/// A snapshot of the interner's counters, as returned by
+/// [`interner_metrics`](crate::interner_metrics).
+///
+/// The counters are read one at a time while other threads may be interning, so a snapshot taken
+/// during heavy use is only approximately consistent.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct InternerMetrics {
+    /// The number of lookups that found their string already interned.
+    pub hits: usize,
+
+    /// The number of lookups that did not find their string.
+    pub misses: usize,
+
+    /// The number of times a thread had to wait for another thread to release a shard's lock.
+    pub contended: usize,
+}
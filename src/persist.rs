@@ -0,0 +1,145 @@
+//! Saving the interner's strings to a file, and mapping them back into memory in a later process.
+//!
+//! A file starts with [`MAGIC`], followed by the state of [`gensym`](crate::Symbol::gensym) and
+//! the number of ids as little-endian `u64`s. Then, for each id in order, there is the length of
+//! its string as a little-endian `u32` followed by the string's bytes, or just [`HOLE`] if the id's
+//! symbol was collected.
+
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::path::Path;
+use std::str;
+
+use crate::{heap, restore_with};
+
+/// The bytes a file starts with, which include the version of the format.
+const MAGIC: &[u8; 8] = b"symbol\0\x01";
+
+/// The length that marks an id with no symbol.
+const HOLE: u32 = u32::MAX;
+
+/// Writes every interned string to a file, which [`load_mmap`] can load in a later process.
+/// Requires the `mmap` feature.
+///
+/// As with [`snapshot`](crate::snapshot), each symbol gets the id it has now when the file is
+/// loaded.
+///
+/// ```
+/// # use symbol::Symbol;
+/// let path = std::env::temp_dir().join(format!("symbols-{}", std::process::id()));
+/// let s = Symbol::from("persisted");
+/// symbol::save(&path).unwrap();
+///
+/// // Normally this would be in a later run of the program, before anything else is interned.
+/// unsafe { symbol::load_mmap(&path) }.unwrap();
+/// assert_eq!(Symbol::try_get("persisted"), Some(s));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn save(path: impl AsRef<Path>) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let entries = heap().entries();
+    out.write_all(MAGIC)?;
+    out.write_all(&(heap().gensym_counter() as u64).to_le_bytes())?;
+    out.write_all(&(entries.len() as u64).to_le_bytes())?;
+    for data in entries {
+        match data.filter(|data| data.is_interned()) {
+            Some(data) => {
+                let s = data.as_str();
+                let len = u32::try_from(s.len())
+                    .ok()
+                    .filter(|&len| len != HOLE)
+                    .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "string too long"))?;
+                out.write_all(&len.to_le_bytes())?;
+                out.write_all(s.as_bytes())?;
+            }
+            None => out.write_all(&HOLE.to_le_bytes())?,
+        }
+    }
+    out.flush()
+}
+
+/// Maps a file written by [`save`] into memory, and interns its strings where they are, without
+/// copying them. Requires the `mmap` feature.
+///
+/// Like [`restore`](crate::restore), this gives each symbol the id it had when the file was saved,
+/// which only works if the symbols already interned are the first ones in the file; otherwise, it
+/// fails with an error wrapping [`InternError::IdConflict`](crate::InternError::IdConflict). The
+/// interner's tables are rebuilt, so each string is still hashed, but nothing is read into memory
+/// until it is used. The mapping is never unmapped. On platforms other than Unix, the file is read
+/// into memory instead.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated for as long as the program runs, since its symbols'
+/// strings are borrowed from it.
+pub unsafe fn load_mmap(path: impl AsRef<Path>) -> io::Result<()> {
+    let bytes = map(path.as_ref())?;
+    let (gensym, strings) = parse(bytes)
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "not a saved interner"))?;
+    restore_with(strings, gensym, |entry, s| entry.insert_static(s))
+        .map_err(|err| io::Error::new(ErrorKind::Other, err))
+}
+
+/// Reads the state of `gensym` and the strings from a file's contents, checking that they are
+/// valid.
+fn parse(mut bytes: &'static [u8]) -> Option<(usize, Vec<Option<&'static str>>)> {
+    let mut take = |len: usize| {
+        let taken = bytes.get(..len)?;
+        bytes = &bytes[len..];
+        Some(taken)
+    };
+    if take(MAGIC.len())? != MAGIC {
+        return None;
+    }
+    let gensym = usize::try_from(u64::from_le_bytes(take(8)?.try_into().ok()?)).ok()?;
+    let count = usize::try_from(u64::from_le_bytes(take(8)?.try_into().ok()?)).ok()?;
+    let mut strings = Vec::new();
+    for _ in 0..count {
+        let len = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        strings.push(match len {
+            HOLE => None,
+            len => Some(str::from_utf8(take(len as usize)?).ok()?),
+        });
+    }
+    Some((gensym, strings))
+}
+
+/// Maps a file into memory for the rest of the program.
+#[cfg(unix)]
+fn map(path: &Path) -> io::Result<&'static [u8]> {
+    use std::os::unix::io::AsRawFd;
+    use std::ptr::null_mut;
+    use std::slice;
+
+    let file = File::open(path)?;
+    let len = usize::try_from(file.metadata()?.len())
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "file too large"))?;
+    if len == 0 {
+        // Empty mappings aren't allowed, but there is nothing to map anyway.
+        return Ok(&[]);
+    }
+    // SAFETY: This makes a new read-only mapping, which can't overlap anything else. It stays
+    // valid after the file is closed.
+    let ptr = unsafe {
+        libc::mmap(
+            null_mut(),
+            len,
+            libc::PROT_READ,
+            libc::MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: The mapping is `len` bytes long and never unmapped, and the caller of `load_mmap`
+    // promises that the file doesn't change.
+    Ok(unsafe { slice::from_raw_parts(ptr.cast(), len) })
+}
+
+/// Reads a file into memory for the rest of the program, where it can't be mapped.
+#[cfg(not(unix))]
+fn map(path: &Path) -> io::Result<&'static [u8]> {
+    Ok(Box::leak(std::fs::read(path)?.into_boxed_slice()))
+}
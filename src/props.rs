@@ -0,0 +1,46 @@
+//! Properties attached to symbols, like the property lists of Lisp symbols.
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+use core::any::Any;
+
+use foldhash::fast::FixedState;
+use hashbrown::HashMap;
+
+use crate::sync::Mutex;
+
+/// A property's value, of whatever type it was set with.
+type Value = Box<dyn Any + Send + Sync>;
+
+/// An interner's properties, keyed by the ids of the symbol and of the property's key.
+pub(crate) struct Props {
+    map: Mutex<HashMap<(u32, u32), Value, FixedState>>,
+}
+
+impl Props {
+    pub const fn new() -> Props {
+        Props {
+            map: Mutex::new(HashMap::with_hasher(FixedState::with_seed(0))),
+        }
+    }
+
+    pub fn get<T: Any + Clone>(&self, sym: u32, key: u32) -> Option<T> {
+        self.map.lock().get(&(sym, key))?.downcast_ref().cloned()
+    }
+
+    pub fn set<T: Any + Send + Sync>(&self, sym: u32, key: u32, value: T) {
+        self.map.lock().insert((sym, key), Box::new(value));
+    }
+
+    pub fn remove(&self, sym: u32, key: u32) -> bool {
+        self.map.lock().remove(&(sym, key)).is_some()
+    }
+
+    /// Removes the properties of symbols for which `keep` returns false, and those whose keys are
+    /// such symbols.
+    pub fn retain(&self, mut keep: impl FnMut(u32) -> bool) {
+        self.map
+            .lock()
+            .retain(|&(sym, key), _| keep(sym) && keep(key));
+    }
+}
@@ -0,0 +1,77 @@
+//! Namespaced symbols.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use crate::Symbol;
+
+/// A name within a namespace, written `ns/name`.
+///
+/// Both parts are symbols, so comparing two of these is two pointer comparisons, and the parts
+/// can be got at without splitting a string.
+///
+/// ```
+/// # use symbol::{QualifiedSymbol, Symbol};
+/// let q = QualifiedSymbol::parse("clojure.core/map").unwrap();
+/// assert_eq!(q.ns, "clojure.core");
+/// assert_eq!(q.name, "map");
+/// assert_eq!(q.to_string(), "clojure.core/map");
+/// assert_eq!(q.to_symbol(), Symbol::from("clojure.core/map"));
+/// assert_eq!(QualifiedSymbol::parse("map"), None);
+/// ```
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct QualifiedSymbol {
+    /// The namespace.
+    pub ns: Symbol,
+
+    /// The name within the namespace.
+    pub name: Symbol,
+}
+
+impl QualifiedSymbol {
+    /// Pairs a namespace with a name.
+    pub fn new(ns: Symbol, name: Symbol) -> QualifiedSymbol {
+        QualifiedSymbol { ns, name }
+    }
+
+    /// Splits a string at the first `/` into a namespace and a name, interning both.
+    ///
+    /// Returns `None` if there is no `/`, or if either side of it is empty, as for `/` itself.
+    pub fn parse(s: &str) -> Option<QualifiedSymbol> {
+        let (ns, name) = s.split_once('/')?;
+        if ns.is_empty() || name.is_empty() {
+            return None;
+        }
+        Some(QualifiedSymbol::new(Symbol::from(ns), Symbol::from(name)))
+    }
+
+    /// Interns the `ns/name` form, for use where a single symbol is needed.
+    pub fn to_symbol(self) -> Symbol {
+        Symbol::from_fmt(format_args!("{}", self))
+    }
+}
+
+impl Debug for QualifiedSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(
+            fmt,
+            "\"{}/{}\"",
+            self.ns.escape_debug(),
+            self.name.escape_debug()
+        )
+    }
+}
+
+impl Display for QualifiedSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{}/{}", self.ns, self.name)
+    }
+}
+
+impl From<QualifiedSymbol> for Symbol {
+    fn from(sym: QualifiedSymbol) -> Symbol {
+        sym.to_symbol()
+    }
+}
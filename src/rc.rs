@@ -0,0 +1,227 @@
+//! Reference-counted symbols, whose strings are freed once nothing uses them.
+//!
+//! These live in a table of their own, separate from the global interner: removing entries would
+//! break the global interner's lock-free lookups. Instead, interning locks the table, and so does
+//! dropping the last handle to a string. Dropping any other handle is just an atomic decrement.
+//! Since lookups hold the lock too, a string's count can't go from zero back to one while it is
+//! being removed.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use foldhash::fast::FixedState;
+use hashbrown::HashTable;
+
+use crate::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use crate::sync::Mutex;
+
+static TABLE: Mutex<HashTable<Entry>> = Mutex::new(HashTable::new());
+
+/// A string interned with a reference count, which is removed from the table and freed when the
+/// last `RcSymbol` for it is dropped.
+///
+/// This suits programs that intern strings from their users, and so can't keep every string they
+/// ever see. In exchange, interning always locks the table, which is shared by every thread, and so
+/// does dropping the last handle to a string; cloning is an atomic increment. An `RcSymbol` is
+/// never equal to a [`Symbol`](crate::Symbol) with the same string, since the two are interned
+/// separately.
+///
+/// ```
+/// # use symbol::RcSymbol;
+/// let a = RcSymbol::new("request-42");
+/// let b = RcSymbol::new("request-42");
+/// assert_eq!(a, b);
+/// assert_eq!(a, "request-42");
+///
+/// drop(a);
+/// assert!(RcSymbol::try_get("request-42").is_some());
+/// drop(b);
+/// assert!(RcSymbol::try_get("request-42").is_none());
+/// ```
+pub struct RcSymbol {
+    ptr: NonNull<RcData>,
+}
+
+/// The shared part of an `RcSymbol`.
+struct RcData {
+    hash: u64,
+    count: AtomicUsize,
+    s: Box<str>,
+}
+
+/// A pointer to an entry in the table.
+struct Entry(NonNull<RcData>);
+
+// SAFETY: Entries are only dereferenced to read their string and hash, which never change, or to
+// update their count, which is atomic.
+unsafe impl Send for Entry {}
+
+impl Entry {
+    fn data(&self) -> &RcData {
+        // SAFETY: An entry is removed from the table before it is freed.
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl RcSymbol {
+    /// Interns a string, or takes another reference to it if it is already interned.
+    pub fn new(s: &str) -> RcSymbol {
+        let hash = hash(s);
+        let mut table = TABLE.lock();
+        if let Some(entry) = table.find(hash, |entry| &*entry.data().s == s) {
+            entry.data().count.fetch_add(1, AtomicOrdering::Relaxed);
+            return RcSymbol { ptr: entry.0 };
+        }
+
+        let ptr = NonNull::from(Box::leak(Box::new(RcData {
+            hash,
+            count: AtomicUsize::new(1),
+            s: s.into(),
+        })));
+        table.insert_unique(hash, Entry(ptr), |entry| entry.data().hash);
+        RcSymbol { ptr }
+    }
+
+    /// Returns a new reference to a string if it is currently interned, without interning it
+    /// otherwise.
+    pub fn try_get(s: &str) -> Option<RcSymbol> {
+        let table = TABLE.lock();
+        let entry = table.find(hash(s), |entry| &*entry.data().s == s)?;
+        entry.data().count.fetch_add(1, AtomicOrdering::Relaxed);
+        Some(RcSymbol { ptr: entry.0 })
+    }
+
+    /// Returns the address of the interned string, which is the same for every handle to it.
+    pub fn addr(&self) -> usize {
+        self.ptr.as_ptr() as usize
+    }
+
+    /// Retrieves the string.
+    pub fn as_str(&self) -> &str {
+        &self.data().s
+    }
+
+    fn data(&self) -> &RcData {
+        // SAFETY: The handle is counted, so the data hasn't been freed.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+fn hash(s: &str) -> u64 {
+    let mut hasher = FixedState::default().build_hasher();
+    s.hash(&mut hasher);
+    hasher.finish()
+}
+
+// SAFETY: The data is only read or updated atomically, and is freed by whichever thread drops the
+// last handle.
+unsafe impl Send for RcSymbol {}
+
+// SAFETY: As above.
+unsafe impl Sync for RcSymbol {}
+
+impl Clone for RcSymbol {
+    fn clone(&self) -> RcSymbol {
+        self.data().count.fetch_add(1, AtomicOrdering::Relaxed);
+        RcSymbol { ptr: self.ptr }
+    }
+}
+
+impl Debug for RcSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(self.as_str(), fmt)
+    }
+}
+
+impl Deref for RcSymbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for RcSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.pad(self.as_str())
+    }
+}
+
+impl Drop for RcSymbol {
+    fn drop(&mut self) {
+        let data = self.data();
+        let mut count = data.count.load(AtomicOrdering::Relaxed);
+        while count > 1 {
+            match data.count.compare_exchange_weak(
+                count,
+                count - 1,
+                AtomicOrdering::Release,
+                AtomicOrdering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(actual) => count = actual,
+            }
+        }
+
+        // This looks like the last handle, but another thread may look the string up before we
+        // get the lock, so only remove it if the count really drops to zero with the lock held.
+        let mut table = TABLE.lock();
+        if data.count.fetch_sub(1, AtomicOrdering::AcqRel) != 1 {
+            return;
+        }
+        if let Ok(entry) = table.find_entry(data.hash, |entry| entry.0 == self.ptr) {
+            entry.remove();
+        }
+        drop(table);
+        // SAFETY: The count is zero and the entry is out of the table, so there are no other
+        // handles and there can't be any new ones.
+        drop(unsafe { Box::from_raw(self.ptr.as_ptr()) });
+    }
+}
+
+impl<'a> From<&'a str> for RcSymbol {
+    fn from(s: &'a str) -> RcSymbol {
+        RcSymbol::new(s)
+    }
+}
+
+impl Eq for RcSymbol {}
+
+/// As with [`Symbol`](crate::Symbol), this hashes the hash computed when the string was interned.
+impl Hash for RcSymbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.data().hash);
+    }
+}
+
+impl Ord for RcSymbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.addr().cmp(&other.addr())
+    }
+}
+
+impl PartialEq for RcSymbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl PartialOrd for RcSymbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: AsRef<str>> PartialEq<S> for RcSymbol {
+    fn eq(&self, other: &S) -> bool {
+        self.as_str() == other.as_ref()
+    }
+}
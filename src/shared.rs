@@ -0,0 +1,137 @@
+//! Sharing one global interner between every copy of this crate in a process, such as the copies
+//! linked into a program and the plugins it loads.
+//!
+//! Each copy exports a record of the shared interner under a name that includes the crate's
+//! version. A copy looks the name up with the dynamic linker, and uses the first record found, or
+//! its own if none is visible; the first copy to need the interner leaks one and publishes it
+//! there. The record is in the process's own memory, so nothing outside the process can point a
+//! copy at an interner.
+//!
+//! Every copy runs its own code on the shared interner, whose layout is up to the compiler, so
+//! copies only share it if they were built the same way: by the same compiler, for the same target,
+//! with the same features and flags, as recorded by the build script. A copy built any other way
+//! uses an interner of its own.
+//!
+//! The dynamic linker only finds records in the program and the libraries in its global scope, so
+//! a program has to export its symbols to share its interner with its plugins, for example by
+//! linking with `-C link-args=-rdynamic`. Off Unix, each copy uses its own record.
+
+use std::mem::size_of;
+use std::ptr::null_mut;
+use std::slice;
+
+use crate::interner::{Interner, SymbolData};
+use crate::sync::atomic::{AtomicPtr, Ordering};
+use crate::sync::Mutex;
+
+/// The name the record is exported under, as a C string.
+#[cfg(unix)]
+const NAME: &str = concat!("__SYMBOL_RS_HEAP_", env!("CARGO_PKG_VERSION"), "\0");
+
+/// Where a copy of the crate publishes the shared interner. Every copy of the same version has to
+/// agree on this layout, whichever compiler built it.
+#[repr(C)]
+struct Shared {
+    published: AtomicPtr<Published>,
+}
+
+/// The shared interner, and the [`layout`] of the copy that made it.
+#[repr(C)]
+struct Published {
+    layout_ptr: *const u8,
+    layout_len: usize,
+    interner: *const Interner,
+}
+
+/// This copy's record, which other copies may use instead of their own.
+#[export_name = concat!("__SYMBOL_RS_HEAP_", env!("CARGO_PKG_VERSION"))]
+static SHARED: Shared = Shared {
+    published: AtomicPtr::new(null_mut()),
+};
+
+/// Returns the interner shared by every compatible copy of the crate.
+pub(crate) fn global() -> &'static Interner {
+    static HEAP: AtomicPtr<Interner> = AtomicPtr::new(null_mut());
+    static INIT: Mutex<()> = Mutex::new(());
+
+    // SAFETY: Only leaked interners are stored.
+    if let Some(interner) = unsafe { HEAP.load(Ordering::Acquire).as_ref() } {
+        return interner;
+    }
+    let _init = INIT.lock();
+    // SAFETY: As above.
+    if let Some(interner) = unsafe { HEAP.load(Ordering::Acquire).as_ref() } {
+        return interner;
+    }
+    let interner = find(record());
+    HEAP.store(
+        interner as *const Interner as *mut Interner,
+        Ordering::Release,
+    );
+    interner
+}
+
+/// Returns the first record the dynamic linker can see, or this copy's own.
+fn record() -> &'static Shared {
+    #[cfg(unix)]
+    {
+        // SAFETY: The name is a C string, and only records are exported under it.
+        let found = unsafe { libc::dlsym(libc::RTLD_DEFAULT, NAME.as_ptr().cast()) };
+        // SAFETY: Records are statics, so they live as long as the copy that exported them, which
+        // is loaded for as long as any of its symbols may be used.
+        if let Some(shared) = unsafe { (found as *const Shared).as_ref() } {
+            return shared;
+        }
+    }
+    &SHARED
+}
+
+/// Returns the interner published in a record, publishing one if there is none, or an interner of
+/// this copy's own if the one published isn't compatible.
+fn find(shared: &'static Shared) -> &'static Interner {
+    let layout = Box::leak(layout().into_boxed_str());
+    let mut new: *mut Published = null_mut();
+    loop {
+        // SAFETY: Only leaked records are published, and never unpublished.
+        if let Some(published) = unsafe { shared.published.load(Ordering::Acquire).as_ref() } {
+            // SAFETY: The layout was leaked along with the record.
+            let published_layout =
+                unsafe { slice::from_raw_parts(published.layout_ptr, published.layout_len) };
+            if published_layout != layout.as_bytes() {
+                return Box::leak(Box::new(Interner::new()));
+            }
+            // SAFETY: A copy built the same way leaked this interner, so it is valid forever, and
+            // works the same way ours would.
+            return unsafe { &*published.interner };
+        }
+
+        if new.is_null() {
+            new = Box::into_raw(Box::new(Published {
+                layout_ptr: layout.as_ptr(),
+                layout_len: layout.len(),
+                interner: Box::leak(Box::new(Interner::new())),
+            }));
+        }
+        // If another copy published its interner first, that one is checked and used instead.
+        if shared
+            .published
+            .compare_exchange(null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            // SAFETY: It was just published.
+            return unsafe { &*(*new).interner };
+        }
+    }
+}
+
+/// Describes how this copy of the crate was built, which its interner's layout and contents depend
+/// on.
+fn layout() -> String {
+    format!(
+        "{} {}-{}-{:x}",
+        env!("SYMBOL_BUILD_FINGERPRINT"),
+        size_of::<Interner>(),
+        size_of::<SymbolData>(),
+        Interner::new().hash("symbol"),
+    )
+}
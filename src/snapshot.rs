@@ -0,0 +1,42 @@
+//! Saving the interner's contents, so that a later session can recreate the same symbols.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Every symbol in the interner at some point, along with the state of [`gensym`], from
+/// [`snapshot`](crate::snapshot).
+///
+/// With the `serde` feature, snapshots can be serialized, so that a session can be saved and
+/// [`restore`](crate::restore)d in a later process.
+///
+/// [`gensym`]: crate::Symbol::gensym
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct InternerSnapshot {
+    /// The symbols' strings, in id order. Ids whose symbols were removed by
+    /// [`collect`](crate::collect) or aren't interned are `None`.
+    pub(crate) strings: Vec<Option<String>>,
+
+    /// The next number `gensym` would try.
+    pub(crate) gensym: usize,
+}
+
+impl InternerSnapshot {
+    /// Returns the strings of the symbols in the snapshot, in order of their ids.
+    pub fn strings(&self) -> impl Iterator<Item = &str> {
+        self.strings.iter().flatten().map(|s| &**s)
+    }
+
+    /// Returns the number of symbols in the snapshot.
+    pub fn len(&self) -> usize {
+        self.strings().count()
+    }
+
+    /// Returns whether the snapshot has no symbols in it.
+    pub fn is_empty(&self) -> bool {
+        self.strings().next().is_none()
+    }
+}
@@ -0,0 +1,113 @@
+//! A representation of symbols with a stable layout, for passing them between separately compiled
+//! programs and plugins.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::ops::Deref;
+use std::{slice, str};
+
+use crate::{heap, Interner, Symbol};
+
+/// A symbol laid out as a C struct of a pointer to its string and the string's length, so that it
+/// can be passed across an FFI boundary, such as between a program and a plugin compiled with a
+/// different version of Rust.
+///
+/// It also records the symbol itself, and which interner was in use when it was made. If that is
+/// the receiving side's interner too, for example with the `shared-heap` feature, converting back
+/// to a [`Symbol`] gives back the symbol that was converted, even a [gensym](Symbol::gensym).
+/// Otherwise, the string is interned on the receiving side, without locking if it already has it.
+/// Copies of this crate only share an interner if they were built the same way, so the symbol is
+/// only used as such by a copy that lays it out the same way.
+///
+/// This doesn't implement [`abi_stable`](https://crates.io/crates/abi_stable)'s `StableAbi`, and
+/// there is no `abi_stable` feature: the crate doesn't depend on `abi_stable`. A type deriving
+/// `StableAbi` can hold one of these as an opaque field, with `#[sabi(unsafe_opaque_field)]`.
+///
+/// ```
+/// # use symbol::{StableSymbol, Symbol};
+/// let s = Symbol::from("exported");
+/// let stable = StableSymbol::from(s);
+/// assert_eq!(stable.as_str(), "exported");
+/// assert_eq!(Symbol::from(stable), s);
+///
+/// let uninterned = Symbol::uninterned("exported");
+/// assert_eq!(Symbol::from(StableSymbol::from(uninterned)), uninterned);
+/// ```
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct StableSymbol {
+    ptr: *const u8,
+    len: usize,
+    /// The symbol, from [`Symbol::into_raw`].
+    sym: *const (),
+    /// The address of the interner in use when this was made.
+    interner: *const (),
+}
+
+impl StableSymbol {
+    /// Retrieves the string.
+    pub fn as_str(self) -> &'static str {
+        // SAFETY: These can only be made from a symbol, whose string lives forever.
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.ptr, self.len)) }
+    }
+}
+
+// SAFETY: The string is never written to.
+unsafe impl Send for StableSymbol {}
+
+// SAFETY: As above.
+unsafe impl Sync for StableSymbol {}
+
+impl Debug for StableSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(self.as_str(), fmt)
+    }
+}
+
+impl Deref for StableSymbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for StableSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.pad(self.as_str())
+    }
+}
+
+impl From<Symbol> for StableSymbol {
+    fn from(sym: Symbol) -> StableSymbol {
+        let s = sym.as_str();
+        StableSymbol {
+            ptr: s.as_ptr(),
+            len: s.len(),
+            sym: sym.into_raw(),
+            interner: heap() as *const Interner as *const (),
+        }
+    }
+}
+
+impl From<StableSymbol> for Symbol {
+    fn from(sym: StableSymbol) -> Symbol {
+        if sym.interner == heap() as *const Interner as *const () {
+            // SAFETY: Interners are only shared between copies of this crate built the same way
+            // (see `shared`), so the pointer is one from `into_raw` in a copy with our layout.
+            unsafe { Symbol::from_raw(sym.sym) }
+        } else {
+            Symbol::from(sym.as_str())
+        }
+    }
+}
+
+/// Two of these are equal if their strings are.
+impl PartialEq for StableSymbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr && self.len == other.len || self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for StableSymbol {}
@@ -0,0 +1,317 @@
+//! Symbols for strings known ahead of time, such as a language's keywords, interned the first
+//! time they're needed.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::ptr::null_mut;
+use std::slice;
+
+use crate::sync::atomic::{AtomicPtr, Ordering};
+use crate::{heap, Symbol};
+
+/// The symbols for a fixed list of strings, which can be kept in a `static`.
+///
+/// This is what [`symbol_enum!`] uses, and isn't meant to be used directly.
+#[doc(hidden)]
+pub struct StaticSymbols {
+    names: &'static [&'static str],
+    cache: Cache,
+}
+
+impl StaticSymbols {
+    pub const fn new(names: &'static [&'static str]) -> StaticSymbols {
+        StaticSymbols {
+            names,
+            cache: Cache::new(),
+        }
+    }
+
+    /// Returns the symbol for the string at an index.
+    pub fn get(&self, index: usize) -> Symbol {
+        match self.cache.get(self.names) {
+            Some(syms) => syms[index],
+            None => Symbol::from_static(self.names[index]),
+        }
+    }
+
+    /// Returns the index of a symbol's string, if it is one of them.
+    pub fn find(&self, sym: Symbol) -> Option<usize> {
+        match self.cache.get(self.names) {
+            Some(syms) => syms.iter().position(|&s| s == sym),
+            None => self.names.iter().position(|&name| name == sym.as_str()),
+        }
+    }
+}
+
+/// A symbol that can be kept in a `static`, as [`symbols!`](crate::symbols!) declares them.
+///
+/// The string is interned with [`Symbol::from_static`] the first time the symbol is needed, and
+/// the symbol is kept, so that getting it afterwards is just a load. The symbol is the same one
+/// [`Symbol::from`] gives for the string, whichever interns it first.
+///
+/// ```
+/// # use symbol::{StaticSymbol, Symbol};
+/// static LAMBDA: StaticSymbol = StaticSymbol::new("lambda");
+/// assert_eq!(LAMBDA.get(), Symbol::from("lambda"));
+/// assert_eq!(LAMBDA, Symbol::from("lambda"));
+/// ```
+pub struct StaticSymbol {
+    name: &'static str,
+    cache: Cache,
+}
+
+impl StaticSymbol {
+    /// Creates a symbol for a string, without interning it yet.
+    pub const fn new(name: &'static str) -> StaticSymbol {
+        StaticSymbol {
+            name,
+            cache: Cache::new(),
+        }
+    }
+
+    /// Retrieves the string, without interning it.
+    pub fn as_str(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the symbol, interning the string if this is the first time.
+    pub fn get(&self) -> Symbol {
+        match self.cache.get(slice::from_ref(&self.name)) {
+            Some(syms) => syms[0],
+            None => Symbol::from_static(self.name),
+        }
+    }
+}
+
+impl Debug for StaticSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(self.name, fmt)
+    }
+}
+
+impl Display for StaticSymbol {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.pad(self.name)
+    }
+}
+
+impl<'a> From<&'a StaticSymbol> for Symbol {
+    fn from(sym: &'a StaticSymbol) -> Symbol {
+        sym.get()
+    }
+}
+
+impl PartialEq<Symbol> for StaticSymbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.get() == *other
+    }
+}
+
+impl PartialEq<StaticSymbol> for Symbol {
+    fn eq(&self, other: &StaticSymbol) -> bool {
+        *self == other.get()
+    }
+}
+
+/// The symbols for some strings, interned when first needed.
+struct Cache {
+    interned: AtomicPtr<Interned>,
+}
+
+/// The symbols for the strings, as interned in one interner.
+struct Interned {
+    /// The address of the interner.
+    heap: usize,
+
+    /// The interner's epoch when the strings were interned. If it has changed, the symbols may
+    /// have been freed.
+    epoch: usize,
+
+    syms: Vec<Symbol>,
+}
+
+impl Cache {
+    const fn new() -> Cache {
+        Cache {
+            interned: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    /// Returns the symbols for the strings as interned in the current thread's interner,
+    /// interning them if need be, or `None` if they were interned in another interner (with the
+    /// `isolation` feature). The strings must be the same every time.
+    fn get(&self, names: &[&'static str]) -> Option<&[Symbol]> {
+        let interner = heap();
+        let heap = interner as *const _ as usize;
+        let epoch = interner.epoch();
+        loop {
+            let current = self.interned.load(Ordering::Acquire);
+            // SAFETY: Replaced ones are only freed when their interner removes entries, and nothing
+            // may use the interner while it does.
+            if let Some(interned) = unsafe { current.as_ref() } {
+                if interned.heap != heap {
+                    return None;
+                } else if interned.epoch == epoch {
+                    return Some(&interned.syms[..]);
+                }
+            }
+
+            let new = Box::into_raw(Box::new(Interned {
+                heap,
+                epoch,
+                syms: names
+                    .iter()
+                    .map(|&name| Symbol::from_static(name))
+                    .collect(),
+            }));
+            match self
+                .interned
+                .compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    // Another thread may still be reading the old symbols, so they're kept until
+                    // the interner next removes entries.
+                    if !current.is_null() {
+                        // SAFETY: They were published by this cache, which no longer refers to
+                        // them.
+                        interner.retire(unsafe { Box::from_raw(current) });
+                    }
+                    // SAFETY: They were just published.
+                    return Some(unsafe { &(*new).syms });
+                }
+                // SAFETY: They were never published.
+                Err(_) => drop(unsafe { Box::from_raw(new) }),
+            }
+        }
+    }
+}
+
+/// Declares [`StaticSymbol`]s, each interned the first time it is needed.
+///
+/// ```
+/// use symbol::{symbols, Symbol};
+///
+/// symbols! {
+///     pub DEFINE = "define";
+///     /// The symbol for `quote`.
+///     QUOTE = "quote";
+/// }
+///
+/// assert_eq!(DEFINE.get(), Symbol::from("define"));
+/// assert_eq!(QUOTE.as_str(), "quote");
+/// ```
+#[macro_export]
+macro_rules! symbols {
+    ($($(#[$attr:meta])* $vis:vis $name:ident = $string:literal;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::StaticSymbol = $crate::StaticSymbol::new($string);
+        )*
+    };
+}
+
+/// Returns the symbol for a string literal, interning it only the first time this expression is
+/// evaluated.
+///
+/// This is a [`StaticSymbol`] declared where it is used: each use of the macro keeps its symbol in
+/// a hidden `static`, so later evaluations are just a load. The symbol is the same one
+/// [`Symbol::from`] gives for the string.
+///
+/// ```
+/// use symbol::{lazy_sym, Symbol};
+///
+/// fn is_self(name: Symbol) -> bool {
+///     name == lazy_sym!("self")
+/// }
+///
+/// assert!(is_self(Symbol::from("self")));
+/// assert!(!is_self(Symbol::from("super")));
+/// ```
+#[macro_export]
+macro_rules! lazy_sym {
+    ($string:literal) => {{
+        static SYMBOL: $crate::StaticSymbol = $crate::StaticSymbol::new($string);
+        SYMBOL.get()
+    }};
+}
+
+/// Defines an enum whose variants stand for symbols, like a lexer's keywords.
+///
+/// The enum gets an `as_symbol` method returning each variant's symbol, and a `from_symbol`
+/// method going the other way; `From` converts it to a symbol, too. The symbols are interned
+/// together the first time either method is used, and kept, so neither method interns or compares
+/// strings after that. Attributes on the enum and its variants are passed through.
+///
+/// The enum derives `Clone` and `Copy`, which these methods need; anything else it should derive
+/// or implement, such as `Debug` or `Eq`, is up to its attributes.
+///
+/// This is the way to build a table from symbols known when the program is compiled, such as
+/// keywords to opcodes. A `phf` map can't be keyed by symbols, since they only exist once the
+/// program runs, and `phf`'s hash has to agree with the one computed at compile time, so it would
+/// hash every symbol's string on every lookup; such a map can still be looked up by a symbol's
+/// [`as_str`](crate::Symbol::as_str). `from_symbol` doesn't look at the string at all.
+///
+/// ```
+/// use symbol::{symbol_enum, Symbol};
+///
+/// symbol_enum! {
+///     /// A keyword.
+///     #[derive(Debug, Eq, PartialEq)]
+///     pub Keyword {
+///         If = "if",
+///         Else = "else",
+///     }
+/// }
+///
+/// assert_eq!(Keyword::from_symbol(Symbol::from("else")), Some(Keyword::Else));
+/// assert_eq!(Keyword::from_symbol(Symbol::from("elif")), None);
+/// assert_eq!(Keyword::If.as_symbol(), "if");
+/// assert_eq!(Keyword::ALL, &[Keyword::If, Keyword::Else]);
+/// ```
+#[macro_export]
+macro_rules! symbol_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident {
+            $($(#[$variant_attr:meta])* $variant:ident = $string:literal),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy)]
+        $vis enum $name {
+            $($(#[$variant_attr])* $variant),*
+        }
+
+        impl $name {
+            /// Every variant, in order.
+            pub const ALL: &'static [$name] = &[$($name::$variant),*];
+
+            /// Returns the variant standing for a symbol, if there is one.
+            pub fn from_symbol(sym: $crate::Symbol) -> ::core::option::Option<$name> {
+                $name::symbols().find(sym).map(|index| $name::ALL[index])
+            }
+
+            /// Returns the symbol the variant stands for.
+            pub fn as_symbol(self) -> $crate::Symbol {
+                $name::symbols().get(self as usize)
+            }
+
+            fn symbols() -> &'static $crate::__private::StaticSymbols {
+                static SYMBOLS: $crate::__private::StaticSymbols =
+                    $crate::__private::StaticSymbols::new(&[$($string),*]);
+                &SYMBOLS
+            }
+        }
+
+        impl ::core::convert::From<$name> for $crate::Symbol {
+            fn from(value: $name) -> $crate::Symbol {
+                value.as_symbol()
+            }
+        }
+    };
+}
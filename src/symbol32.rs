@@ -0,0 +1,89 @@
+//! A compact handle for symbols.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::num::NonZeroU32;
+use std::ops::Deref;
+
+use crate::Symbol;
+
+/// A symbol stored in four bytes, for data structures that hold a lot of them.
+///
+/// This holds the symbol's [id](Symbol::id), so converting back to a [`Symbol`] takes a lookup in
+/// the interner's id table. The lookup doesn't lock, but data structures that mostly use their
+/// symbols as strings may still be better off with `Symbol`. Like `Symbol`s, these are ordered by
+/// id.
+///
+/// ```
+/// # use symbol::{Symbol, Symbol32};
+/// # use std::mem::size_of;
+/// let s = Symbol::from("asdf");
+/// let s32 = Symbol32::from(s);
+/// assert_eq!(Symbol::from(s32), s);
+/// assert_eq!(size_of::<Option<Symbol32>>(), 4);
+/// ```
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Symbol32(NonZeroU32);
+
+impl Symbol32 {
+    /// Retrieves the symbol this is a handle for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the symbol has since been removed, by [`unsafe_clear`](crate::unsafe_clear),
+    /// [`collect`](crate::collect), or dropping a [`checkpoint`](crate::checkpoint) guard.
+    /// [`try_symbol`](Symbol32::try_symbol) doesn't.
+    pub fn symbol(self) -> Symbol {
+        self.try_symbol().expect("Symbol32 with an unknown id")
+    }
+
+    /// Retrieves the symbol this is a handle for, if it still exists.
+    ///
+    /// ```
+    /// # use symbol::{Symbol, Symbol32};
+    /// let s32 = Symbol32::from(Symbol::from("try_symbol"));
+    /// assert_eq!(s32.try_symbol(), Some(Symbol::from("try_symbol")));
+    /// ```
+    pub fn try_symbol(self) -> Option<Symbol> {
+        Symbol::from_id(self.0.get() - 1)
+    }
+
+    /// Retrieves the string from the symbol.
+    pub fn as_str(self) -> &'static str {
+        self.symbol().as_str()
+    }
+}
+
+impl Debug for Symbol32 {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.symbol(), fmt)
+    }
+}
+
+impl Deref for Symbol32 {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for Symbol32 {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.symbol(), fmt)
+    }
+}
+
+impl From<Symbol> for Symbol32 {
+    fn from(sym: Symbol) -> Symbol32 {
+        // Ids never reach u32::MAX, so this can't overflow.
+        Symbol32(NonZeroU32::new(sym.id() + 1).unwrap())
+    }
+}
+
+impl From<Symbol32> for Symbol {
+    fn from(sym: Symbol32) -> Symbol {
+        sym.symbol()
+    }
+}
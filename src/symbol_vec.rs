@@ -0,0 +1,173 @@
+//! Vectors indexed by symbols' ids.
+
+#[cfg(not(feature = "std"))]
+use crate::std;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::ops::{Index, IndexMut};
+
+use crate::Symbol;
+
+/// A map from symbols to values, stored as a vector with a slot for each [id](Symbol::id) up to
+/// the largest key.
+///
+/// Looking a symbol up is indexing into the vector, with no hashing or probing, so this suits
+/// tables of attributes that most symbols have, like the types a compiler assigns its variables.
+/// The vector grows as needed when values are inserted.
+///
+/// ```
+/// # use symbol::{Symbol, SymbolVec};
+/// let x = Symbol::from("x");
+/// let y = Symbol::from("y");
+/// let mut types = SymbolVec::new();
+/// types.insert(x, "int");
+/// assert_eq!(types[x], "int");
+/// assert_eq!(types.get(y), None);
+/// *types.get_or_insert_with(y, || "bool") = "float";
+/// assert_eq!(types.iter().collect::<Vec<_>>(), [(x, &"int"), (y, &"float")]);
+/// ```
+#[derive(Clone)]
+pub struct SymbolVec<T> {
+    slots: Vec<Option<T>>,
+    len: usize,
+}
+
+impl<T> SymbolVec<T> {
+    /// Creates an empty vector, which doesn't allocate until something is inserted.
+    pub fn new() -> SymbolVec<T> {
+        SymbolVec {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of symbols with values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no symbol has a value.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every value, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// Returns a symbol's value.
+    pub fn get(&self, sym: Symbol) -> Option<&T> {
+        self.slots.get(sym.id() as usize)?.as_ref()
+    }
+
+    /// Returns a symbol's value, mutably.
+    pub fn get_mut(&mut self, sym: Symbol) -> Option<&mut T> {
+        self.slots.get_mut(sym.id() as usize)?.as_mut()
+    }
+
+    /// Returns whether a symbol has a value.
+    pub fn contains(&self, sym: Symbol) -> bool {
+        self.get(sym).is_some()
+    }
+
+    /// Sets a symbol's value, returning the value it replaced, if any.
+    pub fn insert(&mut self, sym: Symbol, value: T) -> Option<T> {
+        let old = self.slot(sym).replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Returns a symbol's value, first setting it to the result of `default` if it has none.
+    pub fn get_or_insert_with(&mut self, sym: Symbol, default: impl FnOnce() -> T) -> &mut T {
+        if !self.contains(sym) {
+            self.len += 1;
+        }
+        self.slot(sym).get_or_insert_with(default)
+    }
+
+    /// Removes a symbol's value, returning it.
+    pub fn remove(&mut self, sym: Symbol) -> Option<T> {
+        let old = self.slots.get_mut(sym.id() as usize)?.take();
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    /// Iterates over the symbols with values, in id order.
+    ///
+    /// Symbols that have since been removed by [`collect`](crate::collect) are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &T)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(id, slot)| {
+            let value = slot.as_ref()?;
+            Some((Symbol::from_id(id as u32)?, value))
+        })
+    }
+
+    /// Returns a symbol's slot, growing the vector to make one if need be.
+    fn slot(&mut self, sym: Symbol) -> &mut Option<T> {
+        let id = sym.id() as usize;
+        if id >= self.slots.len() {
+            self.slots.resize_with(id + 1, || None);
+        }
+        &mut self.slots[id]
+    }
+}
+
+impl<T: Debug> Debug for SymbolVec<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        fmt.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Default for SymbolVec<T> {
+    fn default() -> SymbolVec<T> {
+        SymbolVec::new()
+    }
+}
+
+impl<T> Extend<(Symbol, T)> for SymbolVec<T> {
+    fn extend<I: IntoIterator<Item = (Symbol, T)>>(&mut self, iter: I) {
+        for (sym, value) in iter {
+            self.insert(sym, value);
+        }
+    }
+}
+
+impl<T> FromIterator<(Symbol, T)> for SymbolVec<T> {
+    fn from_iter<I: IntoIterator<Item = (Symbol, T)>>(iter: I) -> SymbolVec<T> {
+        let mut vec = SymbolVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+/// Returns a symbol's value.
+///
+/// # Panics
+///
+/// Panics if the symbol has no value.
+impl<T> Index<Symbol> for SymbolVec<T> {
+    type Output = T;
+    fn index(&self, sym: Symbol) -> &T {
+        self.get(sym).expect("symbol has no value")
+    }
+}
+
+/// Returns a symbol's value, mutably.
+///
+/// # Panics
+///
+/// Panics if the symbol has no value.
+impl<T> IndexMut<Symbol> for SymbolVec<T> {
+    fn index_mut(&mut self, sym: Symbol) -> &mut T {
+        self.get_mut(sym).expect("symbol has no value")
+    }
+}
@@ -0,0 +1,122 @@
+//! Synchronization primitives.
+//!
+//! With `std`, locks block in the OS, so that a thread holding one while descheduled doesn't leave
+//! every other thread spinning. Without it, they are spin locks.
+//!
+//! Either way, locks can be created in a `const` context, so the global interner can be a plain
+//! `static`.
+//!
+//! Every lock and atomic the crate uses comes from this module, so that they can be replaced in one
+//! place, for example by a model checker's instrumented versions.
+
+pub(crate) use core::sync::atomic;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use self::spin_mutex::{Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+pub(crate) use self::std_mutex::{Mutex, MutexGuard};
+
+#[cfg(feature = "std")]
+mod std_mutex {
+    use std::sync::{self, PoisonError};
+
+    pub(crate) use std::sync::MutexGuard;
+
+    /// A mutex that ignores poisoning.
+    ///
+    /// Nothing the crate does while holding a lock leaves the protected data half-updated if it
+    /// panics, so there's nothing to gain from propagating poison.
+    pub(crate) struct Mutex<T>(sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub const fn new(value: T) -> Mutex<T> {
+            Mutex(sync::Mutex::new(value))
+        }
+
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            match self.0.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(sync::TryLockError::Poisoned(err)) => Some(err.into_inner()),
+                Err(sync::TryLockError::WouldBlock) => None,
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod spin_mutex {
+    use core::cell::UnsafeCell;
+    use core::hint::spin_loop;
+    use core::ops::{Deref, DerefMut};
+
+    use super::atomic::{AtomicBool, Ordering};
+
+    /// A spin lock.
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    // SAFETY: The lock makes sure only one thread at a time has access to the value.
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub const fn new(value: T) -> Mutex<T> {
+            Mutex {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(value),
+            }
+        }
+
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+            loop {
+                if let Some(guard) = self.try_lock() {
+                    return guard;
+                }
+                // Wait for the lock to look free before trying again, so that waiting threads
+                // don't keep taking the cache line from the one holding it.
+                while self.locked.load(Ordering::Relaxed) {
+                    spin_loop();
+                }
+            }
+        }
+
+        pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            self.locked
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .ok()
+                .map(|_| MutexGuard { mutex: self })
+        }
+    }
+
+    /// Access to a locked mutex's value, which unlocks it when dropped.
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<'a, T> Deref for MutexGuard<'a, T> {
+        type Target = T;
+        fn deref(&self) -> &T {
+            // SAFETY: The guard holds the lock.
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<'a, T> DerefMut for MutexGuard<'a, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: The guard holds the lock, and is borrowed mutably.
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<'a, T> Drop for MutexGuard<'a, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Maps from symbols with nested scopes, as a compiler's frontend uses for its environments.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use foldhash::fast::FixedState;
+use hashbrown::HashMap;
+
+use crate::Symbol;
+
+/// A value, with the depth of the scope it was bound in.
+type Binding<V> = (usize, V);
+
+/// A map from symbols to values, in which bindings made in an inner scope shadow those of outer
+/// scopes until the inner scope is popped.
+///
+/// Looking a symbol up is a single hash lookup however deep the scopes are nested: each scope
+/// records the bindings it shadowed, and popping it puts them back.
+///
+/// ```
+/// # use symbol::{Symbol, SymbolTable};
+/// let x = Symbol::from("x");
+/// let y = Symbol::from("y");
+/// let mut table = SymbolTable::new();
+/// table.insert(x, 1);
+///
+/// table.push_scope();
+/// table.insert(x, 2);
+/// table.insert(y, 3);
+/// assert_eq!(table.lookup(x), Some(&2));
+/// assert_eq!(table.current_scope().count(), 2);
+///
+/// table.pop_scope();
+/// assert_eq!(table.lookup(x), Some(&1));
+/// assert_eq!(table.lookup(y), None);
+/// ```
+#[derive(Clone)]
+pub struct SymbolTable<V> {
+    /// The visible binding of each symbol.
+    bindings: HashMap<Symbol, Binding<V>, FixedState>,
+
+    /// For each scope, from the outermost, the symbols bound in it, and the bindings they shadowed.
+    scopes: Vec<Vec<(Symbol, Option<Binding<V>>)>>,
+}
+
+impl<V> SymbolTable<V> {
+    /// Creates a table with just the outermost scope, which is empty.
+    pub fn new() -> SymbolTable<V> {
+        SymbolTable {
+            bindings: HashMap::with_hasher(FixedState::default()),
+            scopes: vec![Vec::new()],
+        }
+    }
+
+    /// Returns the number of scopes pushed and not yet popped, which is zero for the outermost
+    /// scope.
+    pub fn depth(&self) -> usize {
+        self.scopes.len() - 1
+    }
+
+    /// Starts a new innermost scope.
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    /// Ends the innermost scope, removing its bindings and making those they shadowed visible
+    /// again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no scope to pop, since the outermost scope can't be.
+    pub fn pop_scope(&mut self) {
+        assert!(self.depth() > 0, "popped the outermost scope");
+        let scope = self.scopes.pop().unwrap();
+        for (sym, shadowed) in scope.into_iter().rev() {
+            match shadowed {
+                Some(binding) => self.bindings.insert(sym, binding),
+                None => self.bindings.remove(&sym),
+            };
+        }
+    }
+
+    /// Binds a symbol in the innermost scope.
+    ///
+    /// If the symbol was already bound in the innermost scope, that binding is replaced, and its
+    /// value is returned. A binding from an outer scope is shadowed instead, and `None` is
+    /// returned.
+    pub fn insert(&mut self, sym: Symbol, value: V) -> Option<V> {
+        let depth = self.depth();
+        match self.bindings.insert(sym, (depth, value)) {
+            Some((old_depth, old)) if old_depth == depth => Some(old),
+            shadowed => {
+                self.scopes[depth].push((sym, shadowed));
+                None
+            }
+        }
+    }
+
+    /// Returns the value of the innermost binding of a symbol.
+    pub fn lookup(&self, sym: Symbol) -> Option<&V> {
+        self.bindings.get(&sym).map(|(_, value)| value)
+    }
+
+    /// Returns the value of the innermost binding of a symbol, mutably.
+    pub fn lookup_mut(&mut self, sym: Symbol) -> Option<&mut V> {
+        self.bindings.get_mut(&sym).map(|(_, value)| value)
+    }
+
+    /// Returns whether a symbol is bound in the innermost scope, rather than only in an outer one
+    /// or not at all.
+    pub fn is_bound_in_current_scope(&self, sym: Symbol) -> bool {
+        self.bindings
+            .get(&sym)
+            .map_or(false, |&(depth, _)| depth == self.depth())
+    }
+
+    /// Iterates over the bindings made in the innermost scope, in the order they were made.
+    pub fn current_scope(&self) -> impl Iterator<Item = (Symbol, &V)> + '_ {
+        self.scopes[self.depth()]
+            .iter()
+            .map(move |&(sym, _)| (sym, &self.bindings[&sym].1))
+    }
+}
+
+impl<V> Default for SymbolTable<V> {
+    fn default() -> SymbolTable<V> {
+        SymbolTable::new()
+    }
+}
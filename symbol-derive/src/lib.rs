@@ -0,0 +1,128 @@
+//! Derive macros for the [`symbol`](https://docs.rs/symbol) crate.
+//!
+//! These are re-exported by `symbol` with its `derive` feature, which is the way to use them.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Converts between a fieldless enum and the symbols its variants stand for.
+///
+/// This implements `From<Enum> for Symbol`, and `TryFrom<Symbol> for Enum`, which gives back the
+/// symbol as the error if no variant stands for it. Each variant stands for its own name, unless
+/// it has a `#[symbol(rename = "...")]` attribute. As with `symbol_enum!`, the symbols are
+/// interned together the first time either conversion is used, and kept.
+///
+/// ```
+/// use symbol::{Symbol, SymbolEnum};
+///
+/// #[derive(Debug, PartialEq, SymbolEnum)]
+/// enum Op {
+///     #[symbol(rename = "+")]
+///     Add,
+///     #[symbol(rename = "-")]
+///     Sub,
+///     Neg,
+/// }
+///
+/// assert_eq!(Op::try_from(Symbol::from("+")), Ok(Op::Add));
+/// assert_eq!(Op::try_from(Symbol::from("*")), Err(Symbol::from("*")));
+/// assert_eq!(Symbol::from(Op::Neg), "Neg");
+/// ```
+#[proc_macro_derive(SymbolEnum, attributes(symbol))]
+pub fn derive_symbol_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    symbol_enum(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn symbol_enum(input: &DeriveInput) -> Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "SymbolEnum can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut variants = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "SymbolEnum variants can't have fields",
+            ));
+        }
+        let name = symbol_name(variant)?;
+        if names.contains(&name) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("another variant already stands for `{}`", name),
+            ));
+        }
+        variants.push(&variant.ident);
+        names.push(name);
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let indices = 0..variants.len();
+    let indices2 = indices.clone();
+    Ok(quote! {
+        const _: () = {
+            static SYMBOLS: ::symbol::__private::StaticSymbols =
+                ::symbol::__private::StaticSymbols::new(&[#(#names),*]);
+
+            impl #impl_generics ::core::convert::From<#ty #ty_generics> for ::symbol::Symbol
+            #where_clause
+            {
+                fn from(value: #ty #ty_generics) -> ::symbol::Symbol {
+                    SYMBOLS.get(match value {
+                        #(#ty::#variants => #indices,)*
+                    })
+                }
+            }
+
+            impl #impl_generics ::core::convert::TryFrom<::symbol::Symbol> for #ty #ty_generics
+            #where_clause
+            {
+                type Error = ::symbol::Symbol;
+
+                fn try_from(
+                    sym: ::symbol::Symbol,
+                ) -> ::core::result::Result<Self, ::symbol::Symbol> {
+                    match SYMBOLS.find(sym) {
+                        #(::core::option::Option::Some(#indices2) => {
+                            ::core::result::Result::Ok(#ty::#variants)
+                        })*
+                        _ => ::core::result::Result::Err(sym),
+                    }
+                }
+            }
+        };
+    })
+}
+
+/// Returns the name from a variant's `#[symbol(rename = "...")]` attribute, or else its own name.
+fn symbol_name(variant: &syn::Variant) -> Result<String> {
+    let mut name = None;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("symbol") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown symbol attribute"))
+            }
+        })?;
+    }
+    Ok(name.unwrap_or_else(|| variant.ident.to_string()))
+}